@@ -0,0 +1,757 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, SystemTime}
+};
+use chrono::{
+    DateTime, Datelike, Days, Local, Months,
+    NaiveDate, NaiveDateTime, NaiveTime,
+    TimeDelta, TimeZone, Utc, Weekday
+};
+use chrono_tz::Tz;
+use regex::Regex;
+use tao::event_loop::EventLoopProxy;
+
+use crate::{holds::Hold, log, UserEvent};
+
+const MAX_OCCURRENCES: usize = 10_000;
+
+#[derive(Clone)]
+pub struct CalendarFilter {
+    pub path: PathBuf,
+    pub category: Option<String>,
+    pub title: Option<Regex>
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Zone {
+    Utc,
+    Local,
+    Named(Tz)
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly
+}
+
+#[derive(Clone, Debug)]
+struct Recurrence {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<usize>,
+    until: Option<DateTime<Utc>>,
+    by_day: Vec<(Option<i32>, Weekday)>,
+    by_month_day: Vec<i32>,
+    by_month: Vec<u32>,
+    by_set_pos: Vec<i32>
+}
+
+#[derive(Clone, Debug)]
+pub struct Event {
+    uid: Option<String>,
+    summary: String,
+    categories: Vec<String>,
+    zone: Zone,
+    start: NaiveDateTime,
+    duration: TimeDelta,
+    recurrence: Option<Recurrence>,
+    recurrence_id: Option<DateTime<Utc>>,
+    exceptions: Vec<DateTime<Utc>>
+}
+
+impl CalendarFilter {
+    pub fn new(path: &str, category: &str, title: &str) -> Result<Option<Self>, regex::Error> {
+        if path.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(CalendarFilter {
+            path: PathBuf::from(path),
+            category: (!category.is_empty()).then(|| category.to_string()),
+            title: match title {
+                "" => None,
+                title => Some(Regex::new(title)?)
+            }
+        }))
+    }
+}
+
+impl Zone {
+    fn from_tzid(tzid: Option<&str>, value: &str, warnings: &mut Vec<String>) -> Self {
+        if value.ends_with('Z') {
+            return Zone::Utc;
+        }
+
+        let Some(tzid) = tzid.map(|tzid| tzid.trim_matches('"')) else {
+            return Zone::Local;
+        };
+        match tzid.parse::<Tz>() {
+            Ok(tz) => Zone::Named(tz),
+            Err(_) => {
+                warnings.push(format!("unknown time zone '{}', using local time", tzid));
+                Zone::Local
+            }
+        }
+    }
+
+    fn to_utc(&self, naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            Zone::Utc => Some(Utc.from_utc_datetime(&naive)),
+            Zone::Local => Local.from_local_datetime(&naive)
+                .earliest()
+                .map(|dt| dt.with_timezone(&Utc)),
+            Zone::Named(tz) => tz.from_local_datetime(&naive)
+                .earliest()
+                .map(|dt| dt.with_timezone(&Utc))
+        }
+    }
+}
+
+fn parse_date_time(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim_end_matches('Z');
+
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y%m%d").ok()
+                .map(|date| date.and_time(NaiveTime::MIN))
+        })
+}
+
+fn parse_duration(value: &str) -> Option<TimeDelta> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value.trim_start_matches('+'))
+    };
+    let value = value.strip_prefix('P')?;
+
+    let mut seconds: i64 = 0;
+    let mut number = String::new();
+    for c in value.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => {}
+            unit => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                seconds += n * match unit {
+                    'W' => 7 * 24 * 60 * 60,
+                    'D' => 24 * 60 * 60,
+                    'H' => 60 * 60,
+                    'M' => 60,
+                    'S' => 1,
+                    _ => return None
+                };
+            }
+        }
+    }
+
+    let duration = TimeDelta::seconds(seconds);
+    Some(if negative { -duration } else { duration })
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|first| Some((first.checked_add_months(Months::new(1))? - first).num_days() as u32))
+        .unwrap_or_default()
+}
+
+fn parse_weekday(value: &str) -> Option<(Option<i32>, Weekday)> {
+    let split = value.find(|c: char| c.is_ascii_alphabetic())?;
+    let (ordinal, day) = value.split_at(split);
+
+    let ordinal = match ordinal {
+        "" => None,
+        ordinal => match ordinal.trim_start_matches('+').parse::<i32>().ok()? {
+            0 => return None,
+            ordinal if ordinal.abs() > 5 => return None,
+            ordinal => Some(ordinal)
+        }
+    };
+    let day = match day {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None
+    };
+
+    Some((ordinal, day))
+}
+
+fn parse_list<T: std::str::FromStr>(name: &str, value: &str, valid: impl Fn(&T) -> bool) -> Result<Vec<T>, String> {
+    value.split(',')
+        .map(|item| item.trim_start_matches('+').parse::<T>().ok().filter(&valid))
+        .collect::<Option<Vec<T>>>()
+        .ok_or_else(|| format!("invalid {} '{}'", name, value))
+}
+
+fn parse_recurrence(value: &str, zone: Zone) -> Result<Recurrence, String> {
+    let mut recurrence = Recurrence {
+        frequency: Frequency::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        by_day: Vec::new(),
+        by_month_day: Vec::new(),
+        by_month: Vec::new(),
+        by_set_pos: Vec::new()
+    };
+    let mut has_frequency = false;
+    let mut week_start = "MO";
+
+    for part in value.split(';') {
+        let Some((name, value)) = part.split_once('=') else {
+            continue;
+        };
+        let invalid = || format!("invalid {} '{}'", name, value);
+
+        match name {
+            "FREQ" => {
+                has_frequency = true;
+                recurrence.frequency = match value {
+                    "DAILY" => Frequency::Daily,
+                    "WEEKLY" => Frequency::Weekly,
+                    "MONTHLY" => Frequency::Monthly,
+                    "YEARLY" => Frequency::Yearly,
+                    _ => return Err(format!("unsupported frequency '{}'", value))
+                };
+            }
+            "INTERVAL" => {
+                recurrence.interval = value.parse().ok().filter(|interval| *interval > 0).ok_or_else(invalid)?;
+            }
+            "COUNT" => recurrence.count = Some(value.parse().map_err(|_| invalid())?),
+            "UNTIL" => {
+                let until_zone = if value.ends_with('Z') { Zone::Utc } else { zone };
+                recurrence.until = Some(
+                    parse_date_time(value).and_then(|until| until_zone.to_utc(until)).ok_or_else(invalid)?
+                );
+            }
+            "BYDAY" => {
+                recurrence.by_day = value.split(',').map(parse_weekday).collect::<Option<_>>().ok_or_else(invalid)?;
+            }
+            "BYMONTHDAY" => {
+                recurrence.by_month_day = parse_list(name, value, |day: &i32| (1..=31).contains(&day.abs()))?;
+            }
+            "BYMONTH" => recurrence.by_month = parse_list(name, value, |month: &u32| (1..=12).contains(month))?,
+            "BYSETPOS" => {
+                recurrence.by_set_pos = parse_list(name, value, |pos: &i32| (1..=366).contains(&pos.abs()))?;
+            }
+            "WKST" => week_start = value,
+            _ => return Err(format!("unsupported rule part '{}'", name))
+        }
+    }
+
+    if !has_frequency {
+        return Err("missing FREQ".to_string());
+    }
+
+    let has_ordinals = recurrence.by_day.iter().any(|(ordinal, _)| ordinal.is_some());
+    match recurrence.frequency {
+        Frequency::Daily | Frequency::Weekly if has_ordinals => {
+            Err("numbered BYDAY is only supported for MONTHLY and YEARLY rules".to_string())
+        }
+        Frequency::Daily | Frequency::Weekly if !recurrence.by_set_pos.is_empty() => {
+            Err("BYSETPOS is only supported for MONTHLY and YEARLY rules".to_string())
+        }
+        Frequency::Weekly if !recurrence.by_month_day.is_empty() => {
+            Err("BYMONTHDAY is not allowed in WEEKLY rules".to_string())
+        }
+        Frequency::Weekly if week_start != "MO" && recurrence.interval > 1 && !recurrence.by_day.is_empty() => {
+            Err(format!("unsupported week start '{}'", week_start))
+        }
+        Frequency::Yearly if !recurrence.by_day.is_empty() && recurrence.by_month.is_empty() => {
+            Err("BYDAY in YEARLY rules is only supported together with BYMONTH".to_string())
+        }
+        _ => Ok(recurrence)
+    }
+}
+
+impl Recurrence {
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        self.by_day.is_empty() || self.by_day.iter().any(|(ordinal, weekday)| {
+            date.weekday() == *weekday && match ordinal {
+                None => true,
+                Some(n) if *n > 0 => (date.day() as i32 - 1) / 7 + 1 == *n,
+                Some(n) => (days_in_month(date.year(), date.month()) - date.day()) as i32 / 7 + 1 == -n
+            }
+        })
+    }
+
+    fn matches_month_day(&self, date: NaiveDate) -> bool {
+        let days = days_in_month(date.year(), date.month()) as i32;
+
+        self.by_month_day.is_empty() || self.by_month_day.iter().any(|day| {
+            let day = if *day < 0 { days + day + 1 } else { *day };
+            date.day() as i32 == day
+        })
+    }
+
+    fn matches_month(&self, date: NaiveDate) -> bool {
+        self.by_month.is_empty() || self.by_month.contains(&date.month())
+    }
+
+    fn month_days(&self, year: i32, month: u32, day: u32) -> Vec<NaiveDate> {
+        if self.by_day.is_empty() && self.by_month_day.is_empty() {
+            return NaiveDate::from_ymd_opt(year, month, day).into_iter().collect();
+        }
+
+        (1..=days_in_month(year, month))
+            .filter_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+            .filter(|date| self.matches_day(*date) && self.matches_month_day(*date))
+            .collect()
+    }
+
+    fn select(&self, mut dates: Vec<NaiveDate>) -> Vec<NaiveDate> {
+        dates.sort();
+        if self.by_set_pos.is_empty() {
+            return dates;
+        }
+
+        let len = dates.len() as i32;
+        let mut selected: Vec<NaiveDate> = self.by_set_pos.iter()
+            .map(|pos| if *pos > 0 { pos - 1 } else { len + pos })
+            .filter(|index| (0..len).contains(index))
+            .map(|index| dates[index as usize])
+            .collect();
+        selected.sort();
+        selected.dedup();
+        selected
+    }
+
+    fn period(&self, start: NaiveDate, period: u32) -> Option<(NaiveDate, Vec<NaiveDate>)> {
+        let step = period.checked_mul(self.interval)?;
+
+        match self.frequency {
+            Frequency::Daily => {
+                let date = start.checked_add_days(Days::new(step as u64))?;
+                let matches = self.matches_day(date) && self.matches_month_day(date) && self.matches_month(date);
+                Some((date, if matches { vec![date] } else { Vec::new() }))
+            }
+            Frequency::Weekly => {
+                let week = (start - Days::new(start.weekday().num_days_from_monday() as u64))
+                    .checked_add_days(Days::new(step as u64 * 7))?;
+                let days: Vec<Weekday> = match self.by_day.is_empty() {
+                    true => vec![start.weekday()],
+                    false => self.by_day.iter().map(|(_, day)| *day).collect()
+                };
+                let dates = days.into_iter()
+                    .map(|day| week + Days::new(day.num_days_from_monday() as u64))
+                    .filter(|date| self.matches_month(*date))
+                    .collect();
+                Some((week, self.select(dates)))
+            }
+            Frequency::Monthly => {
+                let first = start.with_day(1)?.checked_add_months(Months::new(step))?;
+                if !self.matches_month(first) {
+                    return Some((first, Vec::new()));
+                }
+                Some((first, self.select(self.month_days(first.year(), first.month(), start.day()))))
+            }
+            Frequency::Yearly => {
+                let year = start.year().checked_add(step as i32)?;
+                let first = NaiveDate::from_ymd_opt(year, 1, 1)?;
+                let months: Vec<u32> = match (self.by_month.is_empty(), self.by_month_day.is_empty()) {
+                    (false, _) => self.by_month.clone(),
+                    (true, false) => (1..=12).collect(),
+                    (true, true) => vec![start.month()]
+                };
+                let dates = months.into_iter()
+                    .flat_map(|month| self.month_days(year, month, start.day()))
+                    .collect();
+                Some((first, self.select(dates)))
+            }
+        }
+    }
+}
+
+fn unfold(contents: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for line in contents.lines() {
+        match line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')) {
+            Some(continuation) if !lines.is_empty() => {
+                lines.last_mut().unwrap().push_str(continuation);
+            }
+            _ => lines.push(line.to_string())
+        }
+    }
+
+    lines
+}
+
+fn unescape(value: &str) -> String {
+    value.replace("\\n", "\n")
+        .replace("\\N", "\n")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+pub fn parse(contents: &str) -> (Vec<Event>, Vec<String>) {
+    let mut events: Vec<Event> = Vec::new();
+    let mut warnings: Vec<String> = Vec::new();
+    let mut properties: Option<Vec<(String, Option<String>, String)>> = None;
+
+    for line in unfold(contents) {
+        let Some((head, value)) = line.split_once(':') else {
+            continue;
+        };
+        let mut params = head.split(';');
+        let name = params.next().unwrap_or_default().to_ascii_uppercase();
+        let params: Vec<&str> = params.collect();
+        let tzid = params.iter()
+            .find_map(|param| param.strip_prefix("TZID="))
+            .map(str::to_string);
+
+        if name == "RECURRENCE-ID" && params.contains(&"RANGE=THISANDFUTURE") {
+            warnings.push("RANGE=THISANDFUTURE is not supported, only the single occurrence is replaced".to_string());
+        }
+
+        match (name.as_str(), value) {
+            ("BEGIN", "VEVENT") => properties = Some(Vec::new()),
+            ("END", "VEVENT") => {
+                if let Some(event) = properties.take().and_then(|p| build_event(&p, &mut warnings)) {
+                    events.push(event);
+                }
+            }
+            _ => {
+                if let Some(properties) = properties.as_mut() {
+                    properties.push((name, tzid, value.to_string()));
+                }
+            }
+        }
+    }
+
+    let overrides: Vec<(String, DateTime<Utc>)> = events.iter()
+        .filter_map(|event| Some((event.uid.clone()?, event.recurrence_id?)))
+        .collect();
+    for event in events.iter_mut().filter(|event| event.recurrence.is_some()) {
+        for (uid, recurrence_id) in &overrides {
+            if event.uid.as_ref() == Some(uid) {
+                event.exceptions.push(*recurrence_id);
+            }
+        }
+    }
+
+    let mut unique: Vec<String> = Vec::new();
+    for warning in warnings {
+        if !unique.contains(&warning) {
+            unique.push(warning);
+        }
+    }
+
+    (events, unique)
+}
+
+fn build_event(properties: &[(String, Option<String>, String)], warnings: &mut Vec<String>) -> Option<Event> {
+    let find = |name: &str| properties.iter().find(|(n, _, _)| n == name);
+    let summary = find("SUMMARY").map(|(_, _, v)| unescape(v)).unwrap_or_default();
+
+    let (_, start_tzid, start_value) = find("DTSTART")?;
+    let zone = Zone::from_tzid(start_tzid.as_deref(), start_value, warnings);
+    let start = parse_date_time(start_value)?;
+    let is_all_day = !start_value.contains('T');
+
+    let duration = match (find("DTEND"), find("DURATION")) {
+        (Some((_, end_tzid, end_value)), _) => {
+            let end_zone = Zone::from_tzid(end_tzid.as_deref(), end_value, warnings);
+            let end = end_zone.to_utc(parse_date_time(end_value)?)?;
+            end - zone.to_utc(start)?
+        }
+        (None, Some((_, _, value))) => parse_duration(value)?,
+        (None, None) if is_all_day => TimeDelta::days(1),
+        (None, None) => TimeDelta::zero()
+    };
+
+    let recurrence = match find("RRULE").map(|(_, _, value)| parse_recurrence(value, zone)) {
+        Some(Ok(recurrence)) => Some(recurrence),
+        Some(Err(err)) => {
+            warnings.push(format!("ignoring '{}': {}", summary, err));
+            return None;
+        }
+        None => None
+    };
+
+    let mut parse_instant = |tzid: &Option<String>, value: &str| {
+        let naive = parse_date_time(value)?;
+        Zone::from_tzid(tzid.as_deref(), value, warnings).to_utc(naive)
+    };
+
+    let recurrence_id = find("RECURRENCE-ID").and_then(|(_, tzid, value)| parse_instant(tzid, value));
+
+    let exceptions = properties.iter()
+        .filter(|(name, _, _)| name == "EXDATE")
+        .flat_map(|(_, tzid, value)| value.split(',').map(move |value| (tzid, value)))
+        .filter_map(|(tzid, value)| parse_instant(tzid, value))
+        .collect();
+
+    Some(Event {
+        uid: find("UID").map(|(_, _, v)| v.clone()),
+        summary,
+        categories: properties.iter()
+            .filter(|(name, _, _)| name == "CATEGORIES")
+            .flat_map(|(_, _, value)| value.split(',').map(|c| unescape(c.trim())).collect::<Vec<_>>())
+            .collect(),
+        zone,
+        start,
+        duration,
+        recurrence,
+        recurrence_id,
+        exceptions
+    })
+}
+
+impl Event {
+    fn matches(&self, filter: &CalendarFilter) -> bool {
+        let category_matches = filter.category.as_ref().map_or(true, |category| {
+            self.categories.iter().any(|c| c.eq_ignore_ascii_case(category))
+        });
+        let title_matches = filter.title.as_ref().map_or(true, |title| {
+            title.is_match(&self.summary)
+        });
+
+        category_matches && title_matches
+    }
+
+    fn occurrence_starts(&self, horizon: DateTime<Utc>) -> Vec<NaiveDateTime> {
+        let Some(recurrence) = &self.recurrence else {
+            return vec![self.start];
+        };
+
+        let mut starts: Vec<NaiveDateTime> = Vec::new();
+
+        for period in 0..MAX_OCCURRENCES as u32 {
+            let Some((base, dates)) = recurrence.period(self.start.date(), period) else {
+                break;
+            };
+            if self.zone.to_utc(base.and_time(self.start.time())).is_some_and(|base| base > horizon) {
+                break;
+            }
+
+            for start in dates.into_iter().map(|date| date.and_time(self.start.time())) {
+                if start < self.start {
+                    continue;
+                }
+
+                let past_until = recurrence.until.is_some_and(|until| {
+                    self.zone.to_utc(start).is_some_and(|start| start > until)
+                });
+                let past_count = recurrence.count.is_some_and(|count| starts.len() >= count);
+
+                if past_until || past_count || starts.len() >= MAX_OCCURRENCES {
+                    return starts;
+                }
+                starts.push(start);
+            }
+        }
+
+        starts
+    }
+
+    pub fn is_ongoing(&self, now: DateTime<Utc>) -> bool {
+        self.occurrence_starts(now)
+            .into_iter()
+            .filter_map(|start| self.zone.to_utc(start))
+            .filter(|start| !self.exceptions.contains(start))
+            .any(|start| start <= now && now < start + self.duration)
+    }
+}
+
+pub fn is_busy(events: &[Event], filter: &CalendarFilter, now: DateTime<Utc>) -> bool {
+    events.iter()
+        .filter(|event| event.matches(filter))
+        .any(|event| event.is_ongoing(now))
+}
+
+pub fn watch(
+    filter: Arc<Mutex<Option<CalendarFilter>>>,
+    interval: Duration,
+    proxy: EventLoopProxy<UserEvent>
+) {
+    thread::spawn(move || {
+        let mut last: Option<bool> = None;
+        let mut loaded: Option<(PathBuf, SystemTime)> = None;
+        let mut events: Vec<Event> = Vec::new();
+
+        loop {
+            let filter = filter.lock()
+                .map(|filter| filter.clone())
+                .unwrap_or(None);
+
+            let busy = match filter {
+                Some(filter) => {
+                    let modified = fs::metadata(&filter.path)
+                        .and_then(|metadata| metadata.modified())
+                        .ok();
+
+                    if modified.is_some() && loaded != Some((filter.path.clone(), modified.unwrap())) {
+                        let warnings;
+                        (events, warnings) = fs::read_to_string(&filter.path)
+                            .map(|contents| parse(&contents))
+                            .unwrap_or_default();
+                        for warning in warnings {
+                            log::write(&format!("{}: {}", filter.path.display(), warning));
+                        }
+                        loaded = Some((filter.path.clone(), modified.unwrap()));
+                    }
+
+                    is_busy(&events, &filter, Utc::now())
+                }
+                None => false
+            };
+
+            if last != Some(busy) {
+                if proxy.send_event(UserEvent::TriggerEvent(Hold::Calendar, busy)).is_err() {
+                    break;
+                }
+                last = Some(busy);
+            }
+
+            thread::sleep(interval);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn occurrences(start: &str, rule: &str, count: usize) -> Vec<String> {
+        let event = Event {
+            uid: None,
+            summary: String::new(),
+            categories: Vec::new(),
+            zone: Zone::Utc,
+            start: parse_date_time(start).unwrap(),
+            duration: TimeDelta::hours(1),
+            recurrence: Some(parse_recurrence(rule, Zone::Utc).unwrap()),
+            recurrence_id: None,
+            exceptions: Vec::new()
+        };
+        let horizon = Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap();
+
+        event.occurrence_starts(horizon)
+            .into_iter()
+            .take(count)
+            .map(|start| start.format("%Y-%m-%d").to_string())
+            .collect()
+    }
+
+    fn at(value: &str) -> DateTime<Utc> {
+        Utc.from_utc_datetime(&parse_date_time(value).unwrap())
+    }
+
+    #[test]
+    fn expands_supported_rules() {
+        let cases = [
+            ("20250101T090000Z", "FREQ=DAILY;COUNT=3", vec!["2025-01-01", "2025-01-02", "2025-01-03"]),
+            ("20250101T090000Z", "FREQ=DAILY;INTERVAL=2;UNTIL=20250107T090000Z", vec!["2025-01-01", "2025-01-03", "2025-01-05", "2025-01-07"]),
+            ("20250101T090000Z", "FREQ=DAILY;BYDAY=MO,TU", vec!["2025-01-06", "2025-01-07", "2025-01-13"]),
+            ("20250101T090000Z", "FREQ=WEEKLY;BYDAY=MO,WE,FR;COUNT=4", vec!["2025-01-01", "2025-01-03", "2025-01-06", "2025-01-08"]),
+            ("20250101T090000Z", "FREQ=WEEKLY;INTERVAL=2", vec!["2025-01-01", "2025-01-15", "2025-01-29"]),
+            ("20250131T090000Z", "FREQ=MONTHLY", vec!["2025-01-31", "2025-03-31", "2025-05-31"]),
+            ("20250131T090000Z", "FREQ=MONTHLY;BYMONTHDAY=-1", vec!["2025-01-31", "2025-02-28", "2025-03-31"]),
+            ("20250101T090000Z", "FREQ=MONTHLY;BYMONTHDAY=1,15", vec!["2025-01-01", "2025-01-15", "2025-02-01"]),
+            ("20250114T090000Z", "FREQ=MONTHLY;BYDAY=2TU", vec!["2025-01-14", "2025-02-11", "2025-03-11"]),
+            ("20250131T090000Z", "FREQ=MONTHLY;BYDAY=-1FR", vec!["2025-01-31", "2025-02-28", "2025-03-28"]),
+            ("20250131T090000Z", "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1", vec!["2025-01-31", "2025-02-28", "2025-03-31"]),
+            ("20250613T090000Z", "FREQ=MONTHLY;BYDAY=FR;BYMONTHDAY=13", vec!["2025-06-13", "2026-02-13", "2026-03-13"]),
+            ("20240229T090000Z", "FREQ=YEARLY;COUNT=2", vec!["2024-02-29", "2028-02-29"]),
+            ("20250511T090000Z", "FREQ=YEARLY;BYMONTH=5;BYDAY=2SU", vec!["2025-05-11", "2026-05-10", "2027-05-09"]),
+            ("20250115T090000Z", "FREQ=YEARLY;BYMONTH=1,7", vec!["2025-01-15", "2025-07-15", "2026-01-15"])
+        ];
+
+        for (start, rule, expected) in cases {
+            assert_eq!(occurrences(start, rule, expected.len()), expected, "{}", rule);
+        }
+    }
+
+    #[test]
+    fn rejects_unsupported_rules() {
+        let rules = [
+            "INTERVAL=2",
+            "FREQ=HOURLY",
+            "FREQ=DAILY;BYHOUR=9",
+            "FREQ=WEEKLY;BYWEEKNO=3",
+            "FREQ=YEARLY;BYYEARDAY=100",
+            "FREQ=WEEKLY;BYDAY=1MO",
+            "FREQ=DAILY;BYSETPOS=1",
+            "FREQ=WEEKLY;BYMONTHDAY=1",
+            "FREQ=YEARLY;BYDAY=MO",
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,SU;WKST=SU",
+            "FREQ=MONTHLY;BYDAY=0MO",
+            "FREQ=MONTHLY;BYMONTHDAY=32",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=DAILY;UNTIL=tomorrow"
+        ];
+
+        for rule in rules {
+            assert!(parse_recurrence(rule, Zone::Utc).is_err(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn skips_events_with_unsupported_rules() {
+        let contents = "BEGIN:VEVENT\nSUMMARY:Standup\nDTSTART:20250101T090000Z\nRRULE:FREQ=HOURLY\nEND:VEVENT\n";
+        let (events, warnings) = parse(contents);
+
+        assert!(events.is_empty());
+        assert_eq!(warnings, ["ignoring 'Standup': unsupported frequency 'HOURLY'"]);
+    }
+
+    #[test]
+    fn warns_about_unknown_time_zones() {
+        let contents = "BEGIN:VEVENT\nDTSTART;TZID=W. Europe Standard Time:20250101T090000\nEND:VEVENT\n";
+        let (events, warnings) = parse(contents);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(warnings, ["unknown time zone 'W. Europe Standard Time', using local time"]);
+    }
+
+    #[test]
+    fn moves_overridden_occurrences() {
+        let contents = "\
+BEGIN:VEVENT
+UID:standup
+DTSTART:20250106T090000Z
+DTEND:20250106T093000Z
+RRULE:FREQ=WEEKLY;BYDAY=MO
+EXDATE:20250120T090000Z
+END:VEVENT
+BEGIN:VEVENT
+UID:standup
+RECURRENCE-ID:20250113T090000Z
+DTSTART:20250114T150000Z
+DTEND:20250114T153000Z
+END:VEVENT
+";
+        let (events, warnings) = parse(contents);
+        let filter = CalendarFilter::new("calendar.ics", "", "").unwrap().unwrap();
+
+        assert!(warnings.is_empty());
+        assert!(is_busy(&events, &filter, at("20250106T091000")));
+        assert!(!is_busy(&events, &filter, at("20250113T091000")));
+        assert!(is_busy(&events, &filter, at("20250114T151000")));
+        assert!(!is_busy(&events, &filter, at("20250120T091000")));
+        assert!(is_busy(&events, &filter, at("20250127T091000")));
+    }
+
+    #[test]
+    fn resolves_named_time_zones() {
+        let contents = "BEGIN:VEVENT\nDTSTART;TZID=Europe/Berlin:20250701T090000\nDURATION:PT1H\nEND:VEVENT\n";
+        let (events, _) = parse(contents);
+        let filter = CalendarFilter::new("calendar.ics", "", "").unwrap().unwrap();
+
+        assert!(is_busy(&events, &filter, at("20250701T073000")));
+        assert!(!is_busy(&events, &filter, at("20250701T093000")));
+    }
+}
@@ -0,0 +1,618 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf}
+};
+use regex::Regex;
+
+#[cfg(target_os = "windows")]
+use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+use crate::{
+    ipc,
+    preferences::{config, keys, Preferences, Value},
+    schedule::{Exception, Rule},
+    network,
+    paths,
+    triggers::{self, network::Location, rules, usb::UsbId}
+};
+
+const USAGE: &str = "\
+usage: kava [--config-dir <directory>] [command]
+
+       kava schedule list
+       kava schedule add \"<days> <start>-<end>\"   e.g. \"mon-fri 08:30-18:00\", \"sat,sun 00:00-00:00\" for all day
+       kava schedule remove <number>
+       kava schedule except <YYYY-MM-DD> [label]
+       kava schedule clear
+       kava calendar show
+       kava calendar file <path.ics>
+       kava calendar category <name>
+       kava calendar title <regex>
+       kava calendar clear
+       kava sentinel list
+       kava sentinel add <directory>
+       kava sentinel remove <directory>
+       kava fullscreen list
+       kava fullscreen allow <app>
+       kava fullscreen deny <app>
+       kava fullscreen remove <app>
+       kava rules list
+       kava rules add \"<screen|system> <title|app> <pattern>\"   e.g. \"screen title .*Zoom Meeting.*\"
+       kava rules remove <number>
+       kava network show
+       kava network list
+       kava network add \"<name> <ssid|gateway-mac|subnet> <value> [=> <profile>]\"   e.g. \"office ssid CorpWiFi => Work\"
+       kava network remove <number>
+       kava usb list
+       kava usb add <vendor>:<product>   e.g. 046d:c52b
+       kava usb remove <vendor>:<product>
+       kava profile list
+       kava profile use <name>
+       kava profile show <name>
+       kava profile save <name>
+       kava profile delete <name>
+       kava preferences show
+       kava preferences reset <name|all>
+       kava config export [file]
+       kava config import <file>
+       kava config check [file]
+
+While a *.kava-hold file exists in a watched directory kava stays awake.
+The file may contain 'reason=<text>', 'expires=<RFC 3339 time or unix
+seconds>' and 'pid=<process id>' lines; expired files and files whose
+process has exited are removed.
+
+Preferences are stored in the per-user config directory (%APPDATA%\\kava
+or $XDG_CONFIG_HOME/kava); --config-dir or KAVA_CONFIG_DIR overrides it.
+While kava is running, commands are carried out by the running app so
+their changes take effect immediately.
+Settings in kava.toml next to the preferences override the stored ones
+and are picked up by the running app as soon as the file changes;
+'kava config export' writes every setting in that format.
+Administrators can lock settings and cap hold durations machine-wide in
+/etc/kava/policy.toml or the HKLM\\SOFTWARE\\Policies\\kava registry key.
+KAVA_PREF_<NAME> environment variables override single preferences for
+the current run, e.g. KAVA_PREF_IDLE_TIMEOUT=15m.";
+
+const COMMANDS: [&str; 10] = [
+    "schedule",
+    "calendar",
+    "sentinel",
+    "fullscreen",
+    "rules",
+    "network",
+    "usb",
+    "profile",
+    "preferences",
+    "config"
+];
+
+pub struct Invocation {
+    pub cwd: PathBuf,
+    pub args: Vec<String>
+}
+
+#[derive(Default)]
+pub struct Output {
+    pub code: i32,
+    pub stdout: String,
+    pub stderr: String
+}
+
+impl Output {
+    fn print(&mut self, text: &str) {
+        self.stdout.push_str(text);
+    }
+
+    fn println(&mut self, text: &str) {
+        self.stdout.push_str(text);
+        self.stdout.push('\n');
+    }
+
+    fn fail(&mut self, message: &str) -> i32 {
+        self.stderr.push_str(&format!("kava: {}\n", message));
+        1
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn attach_console() {
+    unsafe {
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn attach_console() {}
+
+pub fn run(args: &[String]) -> Option<i32> {
+    match args.first().map(String::as_str) {
+        Some("--config-dir") => match args.get(1) {
+            Some(dir) => {
+                paths::set_config_dir(PathBuf::from(dir));
+                run(&args[2..])
+            }
+            None => {
+                attach_console();
+                eprintln!("kava: {}", USAGE);
+                Some(1)
+            }
+        },
+        Some(command) if COMMANDS.contains(&command) => {
+            attach_console();
+
+            let invocation = Invocation {
+                cwd: env::current_dir().unwrap_or_default(),
+                args: args.to_vec()
+            };
+            let output = standalone(&invocation)
+                .or_else(|| ipc::send(&invocation).ok())
+                .unwrap_or_else(|| execute_locally(&invocation));
+
+            print!("{}", output.stdout);
+            eprint!("{}", output.stderr);
+            Some(output.code)
+        }
+        Some("help") | Some("--help") => {
+            attach_console();
+            println!("{}", USAGE);
+            Some(0)
+        }
+        _ => None
+    }
+}
+
+fn standalone(invocation: &Invocation) -> Option<Output> {
+    let mut out = Output::default();
+
+    out.code = match (invocation.args[0].as_str(), invocation.args.get(1).map(String::as_str)) {
+        ("network", Some("show")) => network_status(&mut out),
+        ("config", Some("check")) => check_config(&invocation.cwd, &invocation.args[2..], &mut out),
+        _ => return None
+    };
+
+    Some(out)
+}
+
+fn execute_locally(invocation: &Invocation) -> Output {
+    let _ = paths::create_config_dir();
+    let preferences = Preferences::load();
+    if !preferences.is_saved() {
+        let mut out = Output::default();
+        out.code = out.fail(preferences.problem().unwrap_or("preferences are unavailable"));
+        return out;
+    }
+    let _ = preferences.init();
+
+    execute(&preferences, invocation)
+}
+
+pub fn execute(preferences: &Preferences, invocation: &Invocation) -> Output {
+    let mut out = Output::default();
+    let cwd = invocation.cwd.as_path();
+    let args = &invocation.args[1.min(invocation.args.len())..];
+
+    out.code = match invocation.args.first().map(String::as_str) {
+        Some("schedule") => schedule(preferences, args, &mut out),
+        Some("calendar") => calendar(preferences, cwd, args, &mut out),
+        Some("sentinel") => sentinel(preferences, cwd, args, &mut out),
+        Some("fullscreen") => fullscreen(preferences, args, &mut out),
+        Some("rules") => window_rules(preferences, args, &mut out),
+        Some("network") => network_locations(preferences, args, &mut out),
+        Some("usb") => usb_devices(preferences, args, &mut out),
+        Some("profile") => profile(preferences, args, &mut out),
+        Some("preferences") => diagnostics(preferences, args, &mut out),
+        Some("config") => config_file(preferences, cwd, args, &mut out),
+        _ => out.fail(USAGE)
+    };
+
+    out
+}
+
+fn schedule(preferences: &Preferences, args: &[String], out: &mut Output) -> i32 {
+    let mut schedule = match preferences.load_schedule() {
+        Ok(schedule) => schedule,
+        Err(err) => return out.fail(&format!("failed to load schedule: {}", err))
+    };
+
+    match args.first().map(String::as_str) {
+        Some("list") | None => {
+            for (index, rule) in schedule.rules.iter().enumerate() {
+                out.println(&format!("{}. {}", index + 1, rule));
+            }
+            for exception in &schedule.exceptions {
+                out.println(&format!("except {} {}", exception.date_key(), exception.label));
+            }
+            return 0;
+        }
+        Some("add") => {
+            match args[1..].join(" ").parse::<Rule>() {
+                Ok(rule) => schedule.rules.push(rule),
+                Err(err) => return out.fail(&err.to_string())
+            }
+        }
+        Some("remove") => {
+            match args.get(1).and_then(|n| n.parse::<usize>().ok()) {
+                Some(n) if n >= 1 && n <= schedule.rules.len() => {
+                    schedule.rules.remove(n - 1);
+                }
+                _ => return out.fail("expected the number of a rule from 'kava schedule list'")
+            }
+        }
+        Some("except") => {
+            let Some(date) = args.get(1) else {
+                return out.fail(USAGE);
+            };
+            match Exception::parse(date, &args[2..].join(" ")) {
+                Ok(exception) => schedule.exceptions.push(exception),
+                Err(err) => return out.fail(&err.to_string())
+            }
+        }
+        Some("clear") => {
+            schedule.rules.clear();
+            schedule.exceptions.clear();
+        }
+        _ => return out.fail(USAGE)
+    }
+
+    match preferences.save_schedule(&schedule) {
+        Ok(()) => 0,
+        Err(err) => out.fail(&format!("failed to save schedule: {}", err))
+    }
+}
+
+fn calendar(preferences: &Preferences, cwd: &Path, args: &[String], out: &mut Output) -> i32 {
+    let value = args[1.min(args.len())..].join(" ");
+    let result = match args.first().map(String::as_str) {
+        Some("show") | None => {
+            for key in [&keys::CALENDAR_PATH, &keys::CALENDAR_CATEGORY, &keys::CALENDAR_TITLE_PATTERN] {
+                out.println(&format!("{} = {}", key.name, preferences.get(key).unwrap_or_default()));
+            }
+            return 0;
+        }
+        Some("file") => match fs::canonicalize(cwd.join(&value)) {
+            Ok(path) => preferences.set(&keys::CALENDAR_PATH, path.to_string_lossy().to_string()),
+            Err(err) => return out.fail(&format!("cannot read '{}': {}", value, err))
+        },
+        Some("category") => preferences.set(&keys::CALENDAR_CATEGORY, value),
+        Some("title") => {
+            if let Err(err) = Regex::new(&value) {
+                return out.fail(&format!("invalid title pattern: {}", err));
+            }
+            preferences.set(&keys::CALENDAR_TITLE_PATTERN, value)
+        }
+        Some("clear") => [&keys::CALENDAR_PATH, &keys::CALENDAR_CATEGORY, &keys::CALENDAR_TITLE_PATTERN]
+            .into_iter()
+            .try_for_each(|key| preferences.set(key, String::new())),
+        _ => return out.fail(USAGE)
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(err) => out.fail(&format!("failed to save calendar settings: {}", err))
+    }
+}
+
+fn sentinel(preferences: &Preferences, cwd: &Path, args: &[String], out: &mut Output) -> i32 {
+    let mut directories = preferences.get(&keys::SENTINEL_DIRECTORIES).unwrap_or_default();
+
+    match (args.first().map(String::as_str), args.get(1)) {
+        (Some("list") | None, _) => {
+            for directory in &directories {
+                out.println(directory);
+            }
+            return 0;
+        }
+        (Some("add"), Some(directory)) => match fs::canonicalize(cwd.join(directory)) {
+            Ok(path) if path.is_dir() => {
+                let path = path.to_string_lossy().to_string();
+                if !directories.contains(&path) {
+                    directories.push(path);
+                }
+            }
+            _ => return out.fail(&format!("'{}' is not a directory", directory))
+        },
+        (Some("remove"), Some(directory)) => {
+            let canonical = fs::canonicalize(cwd.join(directory))
+                .map(|path| path.to_string_lossy().to_string())
+                .unwrap_or_else(|_| directory.clone());
+            directories.retain(|d| d != directory && *d != canonical);
+        }
+        _ => return out.fail(USAGE)
+    }
+
+    match preferences.set(&keys::SENTINEL_DIRECTORIES, directories) {
+        Ok(()) => 0,
+        Err(err) => out.fail(&format!("failed to save sentinel directories: {}", err))
+    }
+}
+
+fn fullscreen(preferences: &Preferences, args: &[String], out: &mut Output) -> i32 {
+    let mut allow = preferences.get(&keys::FULLSCREEN_ALLOW).unwrap_or_default();
+    let mut deny = preferences.get(&keys::FULLSCREEN_DENY).unwrap_or_default();
+
+    match (args.first().map(String::as_str), args.get(1)) {
+        (Some("list") | None, _) => {
+            for app in &allow {
+                out.println(&format!("allow {}", app));
+            }
+            for app in &deny {
+                out.println(&format!("deny {}", app));
+            }
+            return 0;
+        }
+        (Some("allow"), Some(app)) => {
+            deny.retain(|a| !a.eq_ignore_ascii_case(app));
+            if !allow.iter().any(|a| a.eq_ignore_ascii_case(app)) {
+                allow.push(app.clone());
+            }
+        }
+        (Some("deny"), Some(app)) => {
+            allow.retain(|a| !a.eq_ignore_ascii_case(app));
+            if !deny.iter().any(|a| a.eq_ignore_ascii_case(app)) {
+                deny.push(app.clone());
+            }
+        }
+        (Some("remove"), Some(app)) => {
+            allow.retain(|a| !a.eq_ignore_ascii_case(app));
+            deny.retain(|a| !a.eq_ignore_ascii_case(app));
+        }
+        _ => return out.fail(USAGE)
+    }
+
+    let result = preferences.set(&keys::FULLSCREEN_ALLOW, allow)
+        .and_then(|_| preferences.set(&keys::FULLSCREEN_DENY, deny));
+
+    match result {
+        Ok(()) => 0,
+        Err(err) => out.fail(&format!("failed to save fullscreen apps: {}", err))
+    }
+}
+
+fn window_rules(preferences: &Preferences, args: &[String], out: &mut Output) -> i32 {
+    let mut window_rules = rules::parse_rules(
+        &preferences.get(&keys::WINDOW_RULES).unwrap_or_default()
+    );
+
+    match args.first().map(String::as_str) {
+        Some("list") | None => {
+            for (index, rule) in window_rules.iter().enumerate() {
+                out.println(&format!("{}. {}", index + 1, rule));
+            }
+            return 0;
+        }
+        Some("add") => {
+            match args[1..].join(" ").parse::<rules::Rule>() {
+                Ok(rule) => window_rules.push(rule),
+                Err(err) => return out.fail(&err.to_string())
+            }
+        }
+        Some("remove") => {
+            match args.get(1).and_then(|n| n.parse::<usize>().ok()) {
+                Some(n) if n >= 1 && n <= window_rules.len() => {
+                    window_rules.remove(n - 1);
+                }
+                _ => return out.fail("expected the number of a rule from 'kava rules list'")
+            }
+        }
+        _ => return out.fail(USAGE)
+    }
+
+    let value: Vec<String> = window_rules.iter().map(|rule| rule.to_string()).collect();
+    match preferences.set(&keys::WINDOW_RULES, value) {
+        Ok(()) => 0,
+        Err(err) => out.fail(&format!("failed to save window rules: {}", err))
+    }
+}
+
+fn network_status(out: &mut Output) -> i32 {
+    let current = network::current();
+    out.println(&format!("ssid = {}", current.ssid.unwrap_or_default()));
+    out.println(&format!("gateway = {}", current.gateway.map(|g| g.to_string()).unwrap_or_default()));
+    out.println(&format!("gateway-mac = {}", current.gateway_mac.unwrap_or_default()));
+    0
+}
+
+fn network_locations(preferences: &Preferences, args: &[String], out: &mut Output) -> i32 {
+    let mut locations = triggers::network::parse_locations(
+        &preferences.get(&keys::NETWORK_LOCATIONS).unwrap_or_default()
+    );
+
+    match args.first().map(String::as_str) {
+        Some("list") | None => {
+            for (index, location) in locations.iter().enumerate() {
+                out.println(&format!("{}. {}", index + 1, location));
+            }
+            return 0;
+        }
+        Some("add") => {
+            match args[1..].join(" ").parse::<Location>() {
+                Ok(location) => {
+                    if let Some(profile) = &location.profile {
+                        if !matches!(preferences.profile(profile), Ok(Some(_))) {
+                            return out.fail(&format!("unknown profile '{}', see 'kava profile list'", profile));
+                        }
+                    }
+                    locations.push(location);
+                }
+                Err(err) => return out.fail(&err.to_string())
+            }
+        }
+        Some("remove") => {
+            match args.get(1).and_then(|n| n.parse::<usize>().ok()) {
+                Some(n) if n >= 1 && n <= locations.len() => {
+                    locations.remove(n - 1);
+                }
+                _ => return out.fail("expected the number of a location from 'kava network list'")
+            }
+        }
+        _ => return out.fail(USAGE)
+    }
+
+    let value: Vec<String> = locations.iter().map(|location| location.to_string()).collect();
+    match preferences.set(&keys::NETWORK_LOCATIONS, value) {
+        Ok(()) => 0,
+        Err(err) => out.fail(&format!("failed to save network locations: {}", err))
+    }
+}
+
+fn usb_devices(preferences: &Preferences, args: &[String], out: &mut Output) -> i32 {
+    let mut devices = triggers::usb::parse_ids(
+        &preferences.get(&keys::USB_DEVICES).unwrap_or_default()
+    );
+
+    match (args.first().map(String::as_str), args.get(1).map(|id| id.parse::<UsbId>())) {
+        (Some("list") | None, _) => {
+            for device in &devices {
+                out.println(&device.to_string());
+            }
+            return 0;
+        }
+        (Some("add"), Some(Ok(id))) => {
+            if !devices.contains(&id) {
+                devices.push(id);
+            }
+        }
+        (Some("remove"), Some(Ok(id))) => devices.retain(|device| *device != id),
+        (_, Some(Err(err))) => return out.fail(&err.to_string()),
+        _ => return out.fail(USAGE)
+    }
+
+    let value: Vec<String> = devices.iter().map(|device| device.to_string()).collect();
+    match preferences.set(&keys::USB_DEVICES, value) {
+        Ok(()) => 0,
+        Err(err) => out.fail(&format!("failed to save USB devices: {}", err))
+    }
+}
+
+fn profile(preferences: &Preferences, args: &[String], out: &mut Output) -> i32 {
+    let name = args[1.min(args.len())..].join(" ");
+    let result = match args.first().map(String::as_str) {
+        Some("list") | None => {
+            let active = preferences.get(&keys::ACTIVE_PROFILE).unwrap_or_default();
+            for profile in preferences.profiles().unwrap_or_default() {
+                let marker = if profile == active { "*" } else { " " };
+                out.println(&format!("{} {}", marker, profile));
+            }
+            return 0;
+        }
+        _ if name.is_empty() => return out.fail(USAGE),
+        Some("use") => match preferences.apply_profile(&name) {
+            Ok(false) => return out.fail(&format!("no profile named '{}'", name)),
+            result => result.map(|_| ())
+        },
+        Some("show") => match preferences.profile(&name) {
+            Ok(Some(settings)) => {
+                let settings: Vec<(&str, _)> = settings.iter()
+                    .map(|(key, value)| (key.as_str(), value.clone()))
+                    .collect();
+                out.print(&config::render(&settings));
+                return 0;
+            }
+            Ok(None) => return out.fail(&format!("no profile named '{}'", name)),
+            Err(err) => Err(err)
+        },
+        Some("save") => preferences.save_profile(&name),
+        Some("delete") => match preferences.delete_profile(&name) {
+            Ok(false) => return out.fail(&format!("no profile named '{}'", name)),
+            result => result.map(|_| ())
+        },
+        _ => return out.fail(USAGE)
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(err) => out.fail(&format!("failed to update profiles: {}", err))
+    }
+}
+
+fn diagnostics(preferences: &Preferences, args: &[String], out: &mut Output) -> i32 {
+    let result = match (args.first().map(String::as_str), args.get(1).map(String::as_str)) {
+        (Some("show") | None, _) => {
+            let entries = match preferences.diagnose() {
+                Ok(entries) => entries,
+                Err(err) => return out.fail(&format!("failed to read preferences: {}", err))
+            };
+            for (name, value, source) in entries {
+                out.println(&format!("{:<28} {:<12} {}", name, source, value));
+            }
+            if let Some(problem) = preferences.problem() {
+                return out.fail(problem);
+            }
+            return 0;
+        }
+        (Some("reset"), Some("all")) => preferences.reset_all(),
+        (Some("reset"), Some(name)) => match preferences.reset(name) {
+            Ok(false) => return out.fail(&format!("no preference named '{}'", name)),
+            result => result.map(|_| ())
+        },
+        _ => return out.fail(USAGE)
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(err) => out.fail(&format!("failed to reset preferences: {}", err))
+    }
+}
+
+fn config_path(cwd: &Path, args: &[String]) -> PathBuf {
+    args.first()
+        .map(|path| cwd.join(path))
+        .unwrap_or_else(|| paths::config_file(config::FILE_NAME))
+}
+
+fn read_config(path: &Path, out: &mut Output) -> Result<HashMap<String, Value>, i32> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| out.fail(&format!("cannot read '{}': {}", path.display(), err)))?;
+
+    config::parse(&contents).map_err(|errors| {
+        for error in &errors {
+            out.fail(&format!("{}: {}", path.display(), error));
+        }
+        1
+    })
+}
+
+fn check_config(cwd: &Path, args: &[String], out: &mut Output) -> i32 {
+    match read_config(&config_path(cwd, args), out) {
+        Ok(_) => 0,
+        Err(code) => code
+    }
+}
+
+fn config_file(preferences: &Preferences, cwd: &Path, args: &[String], out: &mut Output) -> i32 {
+    let path = config_path(cwd, &args[1.min(args.len())..]);
+
+    match (args.first().map(String::as_str), args.get(1)) {
+        (Some("import"), Some(_)) => {
+            let values = match read_config(&path, out) {
+                Ok(values) => values,
+                Err(code) => return code
+            };
+
+            match preferences.import(&values) {
+                Ok(()) => 0,
+                Err(err) => out.fail(&format!("failed to import settings: {}", err))
+            }
+        }
+        (Some("export"), _) => {
+            let contents = match preferences.export() {
+                Ok(contents) => contents,
+                Err(err) => return out.fail(&format!("failed to export settings: {}", err))
+            };
+
+            match args.get(1) {
+                Some(_) => match fs::write(&path, contents) {
+                    Ok(()) => 0,
+                    Err(err) => out.fail(&format!("cannot write '{}': {}", path.display(), err))
+                },
+                None => {
+                    out.print(&contents);
+                    0
+                }
+            }
+        }
+        _ => out.fail(USAGE)
+    }
+}
@@ -0,0 +1,86 @@
+use std::time::{Duration, Instant};
+
+pub struct Debounce<T> {
+    delay: Duration,
+    value: T,
+    pending: Option<(T, Instant)>
+}
+
+impl<T: PartialEq> Debounce<T> {
+    pub fn new(delay: Duration, value: T) -> Self {
+        Self {
+            delay,
+            value,
+            pending: None
+        }
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    pub fn update(&mut self, value: T, now: Instant) -> bool {
+        if value == self.value {
+            self.pending = None;
+            return false;
+        }
+
+        match &self.pending {
+            Some((pending, since)) if *pending == value => {
+                if now.duration_since(*since) < self.delay {
+                    return false;
+                }
+                self.value = value;
+                self.pending = None;
+                true
+            }
+            _ => {
+                self.pending = Some((value, now));
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DELAY: Duration = Duration::from_millis(500);
+
+    #[test]
+    fn reports_a_change_once_it_settles() {
+        let start = Instant::now();
+        let mut debounce = Debounce::new(DELAY, 1);
+
+        assert!(!debounce.update(1, start));
+        assert!(!debounce.update(2, start));
+        assert!(!debounce.update(2, start + DELAY / 2));
+        assert!(debounce.update(2, start + DELAY));
+        assert!(!debounce.update(2, start + DELAY * 2));
+        assert_eq!(*debounce.value(), 2);
+    }
+
+    #[test]
+    fn restarts_the_delay_while_still_changing() {
+        let start = Instant::now();
+        let mut debounce = Debounce::new(DELAY, 1);
+
+        assert!(!debounce.update(2, start));
+        assert!(!debounce.update(3, start + DELAY));
+        assert!(!debounce.update(3, start + DELAY + DELAY / 2));
+        assert!(debounce.update(3, start + DELAY * 2));
+    }
+
+    #[test]
+    fn ignores_flapping_values() {
+        let start = Instant::now();
+        let mut debounce = Debounce::new(DELAY, false);
+
+        assert!(!debounce.update(true, start));
+        assert!(!debounce.update(false, start + DELAY / 2));
+        assert!(!debounce.update(true, start + DELAY));
+        assert!(!debounce.update(false, start + DELAY * 2));
+        assert!(!*debounce.value());
+    }
+}
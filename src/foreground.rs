@@ -0,0 +1,208 @@
+#[cfg(target_os = "windows")]
+use windows::{
+    core::PWSTR,
+    Win32::{
+        Foundation::{CloseHandle, HWND, RECT},
+        Graphics::Gdi::{
+            GetMonitorInfoW, MonitorFromWindow,
+            MONITORINFO, MONITOR_DEFAULTTONEAREST
+        },
+        System::Threading::{
+            OpenProcess, QueryFullProcessImageNameW,
+            PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION
+        },
+        UI::WindowsAndMessaging::{
+            GetDesktopWindow, GetForegroundWindow,
+            GetShellWindow, GetWindowRect,
+            GetWindowTextW, GetWindowThreadProcessId
+        }
+    }
+};
+
+#[cfg(target_os = "linux")]
+use x11rb::{
+    protocol::xproto::{Atom, AtomEnum, ConnectionExt, Window},
+    rust_connection::RustConnection
+};
+#[cfg(target_os = "linux")]
+use crate::x11::with_connection;
+
+#[derive(Clone, Debug)]
+pub struct WindowInfo {
+    pub title: String,
+    pub process: String
+}
+
+impl WindowInfo {
+    pub fn is_app(&self, app: &str) -> bool {
+        let app = app.trim();
+        let process = self.process.to_ascii_lowercase();
+        let app = app.to_ascii_lowercase();
+
+        process == app || process.strip_suffix(".exe") == Some(app.as_str())
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn process_name(hwnd: HWND) -> String {
+    let mut pid: u32 = 0;
+
+    unsafe {
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+
+        let Ok(process) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) else {
+            return String::new();
+        };
+
+        let mut buffer = [0u16; 1024];
+        let mut size = buffer.len() as u32;
+        let name = match QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR(buffer.as_mut_ptr()),
+            &mut size
+        ) {
+            Ok(()) => String::from_utf16_lossy(&buffer[..size as usize]),
+            Err(_) => String::new()
+        };
+        let _ = CloseHandle(process);
+
+        std::path::Path::new(&name)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn window_info(hwnd: HWND) -> WindowInfo {
+    let mut title = [0u16; 512];
+
+    unsafe {
+        let len = GetWindowTextW(hwnd, &mut title);
+
+        WindowInfo {
+            title: String::from_utf16_lossy(&title[..len.max(0) as usize]),
+            process: process_name(hwnd)
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn foreground() -> Option<WindowInfo> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_invalid() {
+            return None;
+        }
+
+        Some(window_info(hwnd))
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn fullscreen() -> Vec<WindowInfo> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_invalid() || hwnd == GetShellWindow() || hwnd == GetDesktopWindow() {
+            return Vec::new();
+        }
+
+        let mut window = RECT::default();
+        if GetWindowRect(hwnd, &mut window).is_err() {
+            return Vec::new();
+        }
+
+        let mut monitor = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        let hmonitor = MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST);
+        if !GetMonitorInfoW(hmonitor, &mut monitor).as_bool() {
+            return Vec::new();
+        }
+
+        let covers_monitor = window.left <= monitor.rcMonitor.left &&
+            window.top <= monitor.rcMonitor.top &&
+            window.right >= monitor.rcMonitor.right &&
+            window.bottom >= monitor.rcMonitor.bottom;
+
+        if covers_monitor {
+            vec![window_info(hwnd)]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn atom(conn: &RustConnection, name: &str) -> Option<Atom> {
+    conn.intern_atom(false, name.as_bytes()).ok()?
+        .reply().ok()
+        .map(|reply| reply.atom)
+}
+
+#[cfg(target_os = "linux")]
+fn property32(conn: &RustConnection, window: Window, property: Atom, kind: AtomEnum) -> Vec<u32> {
+    conn.get_property(false, window, property, kind, 0, u32::MAX)
+        .ok()
+        .and_then(|cookie| cookie.reply().ok())
+        .and_then(|reply| reply.value32().map(|values| values.collect()))
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "linux")]
+fn window_info(conn: &RustConnection, window: Window) -> WindowInfo {
+    let title = atom(conn, "_NET_WM_NAME")
+        .zip(atom(conn, "UTF8_STRING"))
+        .and_then(|(name, utf8)| {
+            conn.get_property(false, window, name, utf8, 0, u32::MAX).ok()?
+                .reply().ok()
+        })
+        .map(|reply| String::from_utf8_lossy(&reply.value).to_string())
+        .unwrap_or_default();
+
+    let process = atom(conn, "_NET_WM_PID")
+        .and_then(|pid| property32(conn, window, pid, AtomEnum::CARDINAL).first().copied())
+        .and_then(|pid| std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok())
+        .map(|comm| comm.trim().to_string())
+        .unwrap_or_default();
+
+    WindowInfo { title, process }
+}
+
+#[cfg(target_os = "linux")]
+pub fn foreground() -> Option<WindowInfo> {
+    with_connection(|conn, root| {
+        let active = atom(conn, "_NET_ACTIVE_WINDOW")?;
+        let window = *property32(conn, root, active, AtomEnum::WINDOW).first()?;
+        if window == 0 {
+            return None;
+        }
+
+        Some(window_info(conn, window))
+    })
+}
+
+#[cfg(target_os = "linux")]
+pub fn fullscreen() -> Vec<WindowInfo> {
+    with_connection(|conn, root| {
+        let (Some(client_list), Some(wm_state), Some(fullscreen), Some(hidden)) = (
+            atom(conn, "_NET_CLIENT_LIST"),
+            atom(conn, "_NET_WM_STATE"),
+            atom(conn, "_NET_WM_STATE_FULLSCREEN"),
+            atom(conn, "_NET_WM_STATE_HIDDEN")
+        ) else {
+            return Vec::new();
+        };
+
+        property32(conn, root, client_list, AtomEnum::WINDOW)
+            .into_iter()
+            .filter(|window| {
+                let state = property32(conn, *window, wm_state, AtomEnum::ATOM);
+                state.contains(&fullscreen) && !state.contains(&hidden)
+            })
+            .map(|window| window_info(conn, window))
+            .collect()
+    })
+}
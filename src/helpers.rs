@@ -39,4 +39,4 @@ pub fn set_icon(tray_icon: TrayIcon, theme: Theme, is_activated: bool) {
     };
 
     let _ = tray_icon.set_icon(icon);
-}
\ No newline at end of file
+}
@@ -0,0 +1,248 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant}
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Hold {
+    Manual,
+    Timer,
+    ScreenTimer,
+    RemoteSession,
+    Schedule,
+    Calendar,
+    Sentinel,
+    Fullscreen,
+    WindowRuleScreen,
+    WindowRuleSystem,
+    Network,
+    Usb
+}
+
+const USER_HOLDS: [Hold; 3] = [Hold::Manual, Hold::Timer, Hold::ScreenTimer];
+
+pub const TRIGGER_HOLDS: [Hold; 9] = [
+    Hold::RemoteSession,
+    Hold::Schedule,
+    Hold::Calendar,
+    Hold::Sentinel,
+    Hold::Fullscreen,
+    Hold::WindowRuleScreen,
+    Hold::WindowRuleSystem,
+    Hold::Network,
+    Hold::Usb
+];
+
+impl Hold {
+    pub fn follows_screen_preference(&self) -> bool {
+        !matches!(
+            self,
+            Hold::Timer | Hold::ScreenTimer | Hold::Fullscreen |
+                Hold::WindowRuleScreen | Hold::WindowRuleSystem
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Suspension {
+    BatteryPower,
+    LowBattery,
+    Locked
+}
+
+#[derive(Default)]
+pub struct Holds {
+    active: HashMap<Hold, bool>,
+    idle: HashMap<Hold, bool>,
+    triggered: HashSet<Hold>,
+    suspended: HashSet<Suspension>,
+    screen_forbidden: bool,
+    started: HashMap<Hold, Instant>,
+    expired: HashSet<Hold>,
+    max_duration: Option<Duration>
+}
+
+impl Holds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn acquire(&mut self, hold: Hold, keep_screen_on: bool) {
+        if self.expired.contains(&hold) {
+            return;
+        }
+
+        self.active.insert(hold, keep_screen_on);
+        self.started.entry(hold).or_insert_with(Instant::now);
+    }
+
+    pub fn release(&mut self, hold: Hold) {
+        self.active.remove(&hold);
+        self.idle.remove(&hold);
+        self.started.remove(&hold);
+        self.expired.remove(&hold);
+    }
+
+    pub fn release_all(&mut self) {
+        self.active.clear();
+        self.idle.clear();
+        self.started.clear();
+        self.expired.clear();
+    }
+
+    pub fn set_max_duration(&mut self, max_duration: Option<Duration>) {
+        self.max_duration = max_duration;
+    }
+
+    pub fn expire(&mut self, now: Instant) -> Vec<Hold> {
+        let Some(max_duration) = self.max_duration else {
+            return Vec::new();
+        };
+
+        let expired: Vec<Hold> = self.started.iter()
+            .filter(|(_, started)| now.saturating_duration_since(**started) >= max_duration)
+            .map(|(hold, _)| *hold)
+            .collect();
+        for hold in &expired {
+            self.active.remove(hold);
+            self.idle.remove(hold);
+            self.started.remove(hold);
+            if TRIGGER_HOLDS.contains(hold) {
+                self.expired.insert(*hold);
+            }
+        }
+
+        expired
+    }
+
+    pub fn set_keep_screen_on(&mut self, keep_screen_on: bool) {
+        for (hold, screen) in self.active.iter_mut().chain(self.idle.iter_mut()) {
+            if hold.follows_screen_preference() {
+                *screen = keep_screen_on;
+            }
+        }
+    }
+
+    pub fn set_idle(&mut self, idle: bool) {
+        if idle {
+            for hold in USER_HOLDS {
+                if let Some(keep_screen_on) = self.active.remove(&hold) {
+                    self.idle.insert(hold, keep_screen_on);
+                }
+            }
+        } else {
+            self.active.extend(self.idle.drain());
+        }
+    }
+
+    pub fn is_acquired(&self, hold: Hold) -> bool {
+        self.active.contains_key(&hold) || self.idle.contains_key(&hold)
+    }
+
+    pub fn is_held(&self) -> bool {
+        !self.active.is_empty() && self.suspended.is_empty()
+    }
+
+    pub fn keep_screen_on(&self) -> bool {
+        !self.screen_forbidden && self.active.values().any(|keep_screen_on| *keep_screen_on)
+    }
+
+    pub fn set_screen_forbidden(&mut self, forbidden: bool) {
+        self.screen_forbidden = forbidden;
+    }
+
+    pub fn set_triggered(&mut self, hold: Hold, triggered: bool) {
+        if triggered {
+            self.triggered.insert(hold);
+        } else {
+            self.triggered.remove(&hold);
+        }
+    }
+
+    pub fn is_triggered(&self, hold: Hold) -> bool {
+        self.triggered.contains(&hold)
+    }
+
+    pub fn set_suspended(&mut self, suspension: Suspension, suspended: bool) {
+        if suspended {
+            self.suspended.insert(suspension);
+        } else {
+            self.suspended.remove(&suspension);
+        }
+    }
+
+    pub fn is_suspended(&self, suspension: Suspension) -> bool {
+        self.suspended.contains(&suspension)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX: Duration = Duration::from_secs(60 * 60);
+
+    #[test]
+    fn keeps_holds_without_a_maximum() {
+        let mut holds = Holds::new();
+        holds.acquire(Hold::Manual, false);
+
+        assert!(holds.expire(Instant::now() + MAX * 24).is_empty());
+        assert!(holds.is_held());
+    }
+
+    #[test]
+    fn releases_holds_past_the_maximum() {
+        let mut holds = Holds::new();
+        holds.set_max_duration(Some(MAX));
+        holds.acquire(Hold::Manual, false);
+        holds.acquire(Hold::Timer, false);
+
+        assert!(holds.expire(Instant::now() + MAX / 2).is_empty());
+
+        let mut expired = holds.expire(Instant::now() + MAX);
+        expired.sort_by_key(|hold| *hold as u8);
+        assert_eq!(expired, vec![Hold::Manual, Hold::Timer]);
+        assert!(!holds.is_held());
+
+        holds.acquire(Hold::Manual, false);
+        assert!(holds.is_held());
+    }
+
+    #[test]
+    fn reacquiring_does_not_restart_the_clock() {
+        let mut holds = Holds::new();
+        holds.set_max_duration(Some(MAX));
+        holds.acquire(Hold::Schedule, false);
+        holds.acquire(Hold::Schedule, true);
+
+        assert_eq!(holds.expire(Instant::now() + MAX), vec![Hold::Schedule]);
+    }
+
+    #[test]
+    fn expired_triggers_stay_released_until_they_clear() {
+        let mut holds = Holds::new();
+        holds.set_max_duration(Some(MAX));
+        holds.acquire(Hold::Usb, false);
+        holds.expire(Instant::now() + MAX);
+
+        holds.acquire(Hold::Usb, false);
+        assert!(!holds.is_acquired(Hold::Usb));
+
+        holds.release(Hold::Usb);
+        holds.acquire(Hold::Usb, false);
+        assert!(holds.is_acquired(Hold::Usb));
+    }
+
+    #[test]
+    fn expires_idle_holds() {
+        let mut holds = Holds::new();
+        holds.set_max_duration(Some(MAX));
+        holds.acquire(Hold::Manual, true);
+        holds.set_idle(true);
+
+        assert_eq!(holds.expire(Instant::now() + MAX), vec![Hold::Manual]);
+        holds.set_idle(false);
+        assert!(!holds.is_acquired(Hold::Manual));
+    }
+}
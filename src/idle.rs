@@ -0,0 +1,154 @@
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant}
+};
+use tao::event_loop::EventLoopProxy;
+
+#[cfg(target_os = "windows")]
+use windows::Win32::{
+    System::SystemInformation::GetTickCount,
+    UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO}
+};
+
+#[cfg(target_os = "linux")]
+use std::{
+    env,
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH}
+};
+#[cfg(target_os = "linux")]
+use x11rb::protocol::screensaver::ConnectionExt;
+
+use crate::UserEvent;
+#[cfg(target_os = "linux")]
+use crate::x11::with_connection;
+
+const SYNTHETIC_INPUT_TOLERANCE: Duration = Duration::from_secs(1);
+
+static SYNTHETIC_INPUT: Mutex<Option<(Instant, Duration)>> = Mutex::new(None);
+
+#[cfg(target_os = "windows")]
+fn system_idle_time() -> Option<Duration> {
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        ..Default::default()
+    };
+
+    unsafe {
+        if !GetLastInputInfo(&mut info).as_bool() {
+            return None;
+        }
+
+        Some(Duration::from_millis(GetTickCount().wrapping_sub(info.dwTime) as u64))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn screensaver_idle_time() -> Option<Duration> {
+    with_connection(|conn, root| {
+        let info = conn.screensaver_query_info(root).ok()?.reply().ok()?;
+        Some(Duration::from_millis(info.ms_since_user_input as u64))
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn logind_idle_time() -> Option<Duration> {
+    let session = env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".to_string());
+    let output = Command::new("loginctl")
+        .args(["show-session", &session, "-p", "IdleHint", "-p", "IdleSinceHint"])
+        .output()
+        .ok()?;
+    let output = String::from_utf8_lossy(&output.stdout);
+
+    let property = |name: &str| output.lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.trim().to_string());
+
+    if property("IdleHint")? != "yes" {
+        return Some(Duration::ZERO);
+    }
+
+    let since = Duration::from_micros(property("IdleSinceHint")?.parse().ok()?);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+    Some(now.saturating_sub(since))
+}
+
+#[cfg(target_os = "linux")]
+fn system_idle_time() -> Option<Duration> {
+    screensaver_idle_time().or_else(logind_idle_time)
+}
+
+fn user_idle_time(system_idle: Duration, synthetic: Option<(Duration, Duration)>) -> Duration {
+    match synthetic {
+        Some((since, idle_before)) if system_idle + SYNTHETIC_INPUT_TOLERANCE >= since => idle_before + since,
+        _ => system_idle
+    }
+}
+
+pub fn idle_time() -> Option<Duration> {
+    let system_idle = system_idle_time()?;
+    let synthetic = SYNTHETIC_INPUT.lock().ok()
+        .and_then(|synthetic| *synthetic)
+        .map(|(at, idle_before)| (at.elapsed(), idle_before));
+
+    Some(user_idle_time(system_idle, synthetic))
+}
+
+pub fn record_synthetic_input() {
+    let idle = idle_time();
+    if let Ok(mut synthetic) = SYNTHETIC_INPUT.lock() {
+        *synthetic = idle.map(|idle| (Instant::now(), idle));
+    }
+}
+
+pub fn watch(
+    timeout: Arc<Mutex<Duration>>,
+    interval: Duration,
+    proxy: EventLoopProxy<UserEvent>
+) {
+    thread::spawn(move || {
+        let mut last: Option<bool> = None;
+
+        loop {
+            let after = timeout.lock()
+                .map(|timeout| *timeout)
+                .unwrap_or_default();
+            let idle = !after.is_zero() && idle_time()
+                .is_some_and(|idle| idle >= after);
+
+            if last != Some(idle) {
+                if proxy.send_event(UserEvent::IdleEvent(idle)).is_err() {
+                    break;
+                }
+                last = Some(idle);
+            }
+
+            thread::sleep(interval);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn uses_system_idle_time_without_synthetic_input() {
+        assert_eq!(user_idle_time(5 * MINUTE, None), 5 * MINUTE);
+    }
+
+    #[test]
+    fn ignores_synthetic_input() {
+        assert_eq!(user_idle_time(2 * MINUTE, Some((2 * MINUTE, 10 * MINUTE))), 12 * MINUTE);
+        assert_eq!(user_idle_time(Duration::ZERO, Some((Duration::from_millis(500), 10 * MINUTE))), 10 * MINUTE + Duration::from_millis(500));
+    }
+
+    #[test]
+    fn counts_real_input_after_synthetic_input() {
+        assert_eq!(user_idle_time(MINUTE, Some((2 * MINUTE, 10 * MINUTE))), MINUTE);
+    }
+}
@@ -0,0 +1,306 @@
+use std::{
+    io::{self, Read, Write},
+    path::PathBuf,
+    sync::mpsc::channel,
+    thread,
+    time::Duration
+};
+use tao::event_loop::EventLoopProxy;
+
+#[cfg(target_os = "windows")]
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs::{File, OpenOptions},
+    hash::{Hash, Hasher},
+    os::windows::io::FromRawHandle
+};
+#[cfg(target_os = "windows")]
+use windows::{
+    core::HSTRING,
+    Win32::{
+        Foundation::{CloseHandle, ERROR_PIPE_BUSY, ERROR_PIPE_CONNECTED, HANDLE},
+        Storage::FileSystem::{FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX},
+        System::Pipes::{
+            ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE,
+            PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES,
+            PIPE_WAIT
+        }
+    }
+};
+
+#[cfg(target_os = "linux")]
+use std::{
+    fs,
+    os::unix::{
+        fs::PermissionsExt,
+        net::{UnixListener, UnixStream}
+    }
+};
+
+use crate::{
+    cli::{Invocation, Output},
+    log,
+    paths,
+    UserEvent
+};
+
+const MAX_FIELDS: u32 = 256;
+const MAX_FIELD_LENGTH: u32 = 16 * 1024 * 1024;
+const REPLY_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[cfg(target_os = "linux")]
+const SOCKET_NAME: &str = "kava.sock";
+
+#[cfg(target_os = "windows")]
+const PIPE_BUFFER_SIZE: u32 = 64 * 1024;
+#[cfg(target_os = "windows")]
+const PIPE_BUSY_RETRIES: u32 = 20;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn write_fields(stream: &mut impl Write, fields: &[&str]) -> io::Result<()> {
+    let mut buffer = Vec::new();
+    buffer.extend_from_slice(&(fields.len() as u32).to_le_bytes());
+    for field in fields {
+        buffer.extend_from_slice(&(field.len() as u32).to_le_bytes());
+        buffer.extend_from_slice(field.as_bytes());
+    }
+
+    stream.write_all(&buffer)?;
+    stream.flush()
+}
+
+fn read_u32(stream: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    stream.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_fields(stream: &mut impl Read) -> io::Result<Vec<String>> {
+    let count = read_u32(stream)?;
+    if count > MAX_FIELDS {
+        return Err(invalid("too many fields"));
+    }
+
+    let mut fields = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let length = read_u32(stream)?;
+        if length > MAX_FIELD_LENGTH {
+            return Err(invalid("field is too long"));
+        }
+        let mut bytes = vec![0; length as usize];
+        stream.read_exact(&mut bytes)?;
+        fields.push(String::from_utf8(bytes).map_err(|_| invalid("field is not UTF-8"))?);
+    }
+
+    Ok(fields)
+}
+
+fn handle(stream: &mut (impl Read + Write), proxy: &EventLoopProxy<UserEvent>) -> io::Result<()> {
+    let mut fields = read_fields(stream)?;
+    if fields.len() < 2 {
+        return Err(invalid("expected a directory and a command"));
+    }
+    let invocation = Invocation {
+        cwd: PathBuf::from(fields.remove(0)),
+        args: fields
+    };
+
+    let (tx, rx) = channel();
+    proxy.send_event(UserEvent::CliRequest(invocation, tx))
+        .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "kava is shutting down"))?;
+    let output = rx.recv_timeout(REPLY_TIMEOUT)
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "kava did not answer"))?;
+
+    write_fields(stream, &[&output.code.to_string(), &output.stdout, &output.stderr])
+}
+
+fn request(stream: &mut (impl Read + Write), invocation: &Invocation) -> io::Result<Output> {
+    let cwd = invocation.cwd.to_string_lossy();
+    let mut fields = vec![cwd.as_ref()];
+    fields.extend(invocation.args.iter().map(String::as_str));
+    write_fields(stream, &fields)?;
+
+    match read_fields(stream)?.as_slice() {
+        [code, stdout, stderr] => Ok(Output {
+            code: code.parse().map_err(|_| invalid("invalid exit code"))?,
+            stdout: stdout.clone(),
+            stderr: stderr.clone()
+        }),
+        _ => Err(invalid("expected an exit code and output"))
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn serve(proxy: EventLoopProxy<UserEvent>) {
+    let path = paths::config_file(SOCKET_NAME);
+    if UnixStream::connect(&path).is_ok() {
+        log::write("Another kava instance is already accepting commands");
+        return;
+    }
+
+    let _ = fs::remove_file(&path);
+    let listener = match UnixListener::bind(&path) {
+        Ok(listener) => listener,
+        Err(err) => {
+            log::write(&format!("Failed to listen for commands on {}: {}", path.display(), err));
+            return;
+        }
+    };
+    let _ = fs::set_permissions(&path, fs::Permissions::from_mode(0o600));
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            let _ = stream.set_read_timeout(Some(REPLY_TIMEOUT));
+            if let Err(err) = handle(&mut stream, &proxy) {
+                log::write(&format!("Failed to handle command: {}", err));
+            }
+        }
+    });
+}
+
+#[cfg(target_os = "linux")]
+pub fn send(invocation: &Invocation) -> io::Result<Output> {
+    let mut stream = UnixStream::connect(paths::config_file(SOCKET_NAME))?;
+    stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+    request(&mut stream, invocation)
+}
+
+#[cfg(target_os = "windows")]
+fn pipe_name() -> String {
+    let mut hasher = DefaultHasher::new();
+    paths::config_dir().hash(&mut hasher);
+    format!(r"\\.\pipe\kava-{}-{:016x}", whoami::username(), hasher.finish())
+}
+
+#[cfg(target_os = "windows")]
+fn create_pipe(first: bool) -> io::Result<HANDLE> {
+    let mut mode = PIPE_ACCESS_DUPLEX;
+    if first {
+        mode |= FILE_FLAG_FIRST_PIPE_INSTANCE;
+    }
+
+    let pipe = unsafe {
+        CreateNamedPipeW(
+            &HSTRING::from(pipe_name()),
+            mode,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+            PIPE_UNLIMITED_INSTANCES,
+            PIPE_BUFFER_SIZE,
+            PIPE_BUFFER_SIZE,
+            0,
+            None
+        )
+    };
+    if pipe.is_invalid() {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(pipe)
+}
+
+#[cfg(target_os = "windows")]
+pub fn serve(proxy: EventLoopProxy<UserEvent>) {
+    thread::spawn(move || {
+        let mut pipe = match create_pipe(true) {
+            Ok(pipe) => pipe,
+            Err(err) => {
+                log::write(&format!("Failed to listen for commands: {}", err));
+                return;
+            }
+        };
+
+        loop {
+            if let Err(err) = unsafe { ConnectNamedPipe(pipe, None) } {
+                if err.code() != ERROR_PIPE_CONNECTED.to_hresult() {
+                    log::write(&format!("Failed to accept command: {}", err));
+                    unsafe {
+                        let _ = CloseHandle(pipe);
+                    }
+                    pipe = match create_pipe(false) {
+                        Ok(pipe) => pipe,
+                        Err(_) => return
+                    };
+                    continue;
+                }
+            }
+
+            let next = create_pipe(false);
+            let mut stream = unsafe { File::from_raw_handle(pipe.0) };
+            if let Err(err) = handle(&mut stream, &proxy) {
+                log::write(&format!("Failed to handle command: {}", err));
+            }
+            let _ = stream.sync_all();
+            drop(stream);
+
+            pipe = match next {
+                Ok(pipe) => pipe,
+                Err(err) => {
+                    log::write(&format!("Failed to listen for commands: {}", err));
+                    return;
+                }
+            };
+        }
+    });
+}
+
+#[cfg(target_os = "windows")]
+pub fn send(invocation: &Invocation) -> io::Result<Output> {
+    let name = pipe_name();
+    let mut retries = 0;
+
+    let mut stream = loop {
+        match OpenOptions::new().read(true).write(true).open(&name) {
+            Ok(stream) => break stream,
+            Err(err) if err.raw_os_error() == Some(ERROR_PIPE_BUSY.0 as i32) && retries < PIPE_BUSY_RETRIES => {
+                retries += 1;
+                thread::sleep(Duration::from_millis(50));
+            }
+            Err(err) => return Err(err)
+        }
+    };
+
+    request(&mut stream, invocation)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn fields_round_trip() {
+        let mut buffer = Vec::new();
+        write_fields(&mut buffer, &["/home/user", "schedule", "add", "mon-fri 08:30-18:00", ""]).unwrap();
+
+        let fields = read_fields(&mut Cursor::new(buffer)).unwrap();
+        assert_eq!(fields, ["/home/user", "schedule", "add", "mon-fri 08:30-18:00", ""]);
+    }
+
+    #[test]
+    fn rejects_oversized_messages() {
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(&(MAX_FIELDS + 1).to_le_bytes());
+        assert!(read_fields(&mut Cursor::new(buffer)).is_err());
+
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(&1u32.to_le_bytes());
+        buffer.extend_from_slice(&(MAX_FIELD_LENGTH + 1).to_le_bytes());
+        assert!(read_fields(&mut Cursor::new(buffer)).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_messages() {
+        let mut buffer = Vec::new();
+        write_fields(&mut buffer, &["kava", "schedule"]).unwrap();
+        buffer.truncate(buffer.len() - 2);
+
+        assert!(read_fields(&mut Cursor::new(buffer)).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex
+    },
+    thread,
+    time::{Duration, Instant}
+};
+
+#[cfg(target_os = "windows")]
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    SendInput, INPUT, INPUT_0, INPUT_MOUSE,
+    MOUSEEVENTF_MOVE, MOUSEINPUT
+};
+
+#[cfg(target_os = "linux")]
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    os::{
+        fd::AsRawFd,
+        raw::{c_int, c_ulong}
+    }
+};
+#[cfg(target_os = "linux")]
+use x11rb::{
+    connection::Connection,
+    protocol::{xproto::MOTION_NOTIFY_EVENT, xtest::ConnectionExt},
+    CURRENT_TIME
+};
+
+use crate::idle;
+#[cfg(target_os = "linux")]
+use crate::{log, x11::with_connection};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[cfg(target_os = "linux")]
+const UINPUT_PATH: &str = "/dev/uinput";
+#[cfg(target_os = "linux")]
+const UI_SET_EVBIT: c_ulong = 0x4004_5564;
+#[cfg(target_os = "linux")]
+const UI_SET_RELBIT: c_ulong = 0x4004_5566;
+#[cfg(target_os = "linux")]
+const UI_DEV_CREATE: c_ulong = 0x5501;
+#[cfg(target_os = "linux")]
+const EV_SYN: u16 = 0;
+#[cfg(target_os = "linux")]
+const EV_REL: u16 = 2;
+#[cfg(target_os = "linux")]
+const REL_X: u16 = 0;
+#[cfg(target_os = "linux")]
+const SYN_REPORT: u16 = 0;
+
+#[cfg(target_os = "linux")]
+static UINPUT: Mutex<Option<File>> = Mutex::new(None);
+
+#[cfg(target_os = "linux")]
+extern "C" {
+    fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+}
+
+#[cfg(target_os = "linux")]
+#[repr(C)]
+struct InputEvent {
+    seconds: isize,
+    microseconds: isize,
+    kind: u16,
+    code: u16,
+    value: i32
+}
+
+#[cfg(target_os = "windows")]
+pub fn nudge() {
+    let input = INPUT {
+        r#type: INPUT_MOUSE,
+        Anonymous: INPUT_0 {
+            mi: MOUSEINPUT {
+                dwFlags: MOUSEEVENTF_MOVE,
+                ..Default::default()
+            }
+        }
+    };
+
+    unsafe {
+        SendInput(&[input], std::mem::size_of::<INPUT>() as i32);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn xtest_nudge() -> bool {
+    with_connection(|conn, root| {
+        conn.xtest_fake_input(MOTION_NOTIFY_EVENT, 1, CURRENT_TIME, root, 0, 0, 0).is_ok() && conn.flush().is_ok()
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn create_uinput_device() -> io::Result<File> {
+    let mut device = OpenOptions::new().write(true).open(UINPUT_PATH)?;
+    let fd = device.as_raw_fd();
+
+    for (request, value) in [(UI_SET_EVBIT, EV_SYN), (UI_SET_EVBIT, EV_REL), (UI_SET_RELBIT, REL_X)] {
+        if unsafe { ioctl(fd, request, value as c_int) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    // struct uinput_user_dev: name[80], input_id, ff_effects_max and four absolute axis tables
+    let mut setup = vec![0u8; 80 + 8 + 4 + 4 * 64 * 4];
+    setup[..4].copy_from_slice(b"kava");
+    setup[80..82].copy_from_slice(&0x06u16.to_ne_bytes());
+    device.write_all(&setup)?;
+
+    if unsafe { ioctl(fd, UI_DEV_CREATE) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(device)
+}
+
+#[cfg(target_os = "linux")]
+fn write_events(device: &mut File, events: &[(u16, u16, i32)]) -> io::Result<()> {
+    for (kind, code, value) in events {
+        let event = InputEvent {
+            seconds: 0,
+            microseconds: 0,
+            kind: *kind,
+            code: *code,
+            value: *value
+        };
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                &event as *const InputEvent as *const u8,
+                std::mem::size_of::<InputEvent>()
+            )
+        };
+        device.write_all(bytes)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn uinput_nudge() -> io::Result<()> {
+    let mut uinput = UINPUT.lock().map_err(|_| io::Error::other("uinput device is poisoned"))?;
+    if uinput.is_none() {
+        *uinput = Some(create_uinput_device()?);
+    }
+
+    let device = uinput.as_mut().unwrap();
+    let result = write_events(device, &[
+        (EV_REL, REL_X, 1),
+        (EV_SYN, SYN_REPORT, 0),
+        (EV_REL, REL_X, -1),
+        (EV_SYN, SYN_REPORT, 0)
+    ]);
+    if result.is_err() {
+        *uinput = None;
+    }
+
+    result
+}
+
+#[cfg(target_os = "linux")]
+pub fn nudge() {
+    if xtest_nudge() {
+        return;
+    }
+
+    if let Err(err) = uinput_nudge() {
+        log::write(&format!("Failed to send synthetic input through {}: {}", UINPUT_PATH, err));
+    }
+}
+
+pub fn start(active: Arc<AtomicBool>, interval: Arc<Mutex<Duration>>) {
+    thread::spawn(move || {
+        let mut last = Instant::now();
+
+        loop {
+            thread::sleep(CHECK_INTERVAL);
+
+            let every = interval.lock()
+                .map(|interval| *interval)
+                .unwrap_or_default();
+            if every.is_zero() || !active.load(Ordering::Relaxed) {
+                last = Instant::now();
+                continue;
+            }
+
+            if last.elapsed() >= every {
+                idle::record_synthetic_input();
+                nudge();
+                last = Instant::now();
+            }
+        }
+    });
+}
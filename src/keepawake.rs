@@ -1,34 +1,66 @@
-use std::{thread, time::Duration, sync::mpsc::Sender};
+use std::{
+    collections::HashMap,
+    thread,
+    time::{Duration, SystemTime}
+};
+
+#[cfg(target_os = "linux")]
+use std::{
+    io,
+    process::{Child, Command, Stdio},
+    sync::Mutex
+};
+
+use crate::holds::Hold;
+#[cfg(target_os = "windows")]
 use windows::{
     core::Error as WindowsError,
     Win32::System::Power::{
         SetThreadExecutionState, ES_DISPLAY_REQUIRED,
         ES_SYSTEM_REQUIRED, EXECUTION_STATE,
-        ES_CONTINUOUS
+        ES_CONTINUOUS, ES_AWAYMODE_REQUIRED
     }
 };
+#[cfg(target_os = "windows")]
+use windows_registry::LOCAL_MACHINE;
 
-#[derive(Clone)]
+#[cfg(target_os = "windows")]
+const POWER_REGKEY: &str = r"SYSTEM\CurrentControlSet\Control\Session Manager\Power";
+#[cfg(target_os = "windows")]
+const AWAY_MODE_VALUE: &str = "AwayModeEnabled";
+
+#[cfg(target_os = "linux")]
+const INHIBIT_REASON: &str = "Keeping the system awake";
+#[cfg(any(test, target_os = "linux"))]
+const INHIBIT_WHO: &str = "kava";
+
+#[cfg(target_os = "windows")]
 pub struct KeepAwake {
-    previous: EXECUTION_STATE
+    applied: Option<EXECUTION_STATE>,
+    away_mode: bool
 }
 
+#[cfg(target_os = "windows")]
 impl Drop for KeepAwake {
     fn drop(&mut self) {
-        unsafe {
-            SetThreadExecutionState(self.previous);
-        }
+        self.deactivate();
     }
 }
 
+#[cfg(target_os = "windows")]
 impl KeepAwake {
     pub fn new() -> Result<Self, WindowsError> {
         Ok(KeepAwake {
-            previous: Default::default()
+            applied: None,
+            away_mode: false
         })
     }
 
-    pub fn activate(&mut self, keep_screen_on: bool) -> Result<(), WindowsError> {
+    pub fn set_away_mode(&mut self, away_mode: bool) {
+        self.away_mode = away_mode && supports_away_mode();
+    }
+
+    fn flags(&self, keep_screen_on: bool) -> EXECUTION_STATE {
         let mut esflags = ES_CONTINUOUS;
         esflags |= ES_SYSTEM_REQUIRED;
 
@@ -36,27 +68,299 @@ impl KeepAwake {
             esflags |= ES_DISPLAY_REQUIRED;
         }
 
+        if self.away_mode {
+            esflags |= ES_AWAYMODE_REQUIRED;
+        }
+
+        esflags
+    }
+
+    pub fn activate(&mut self, keep_screen_on: bool) -> Result<(), WindowsError> {
+        let esflags = self.flags(keep_screen_on);
+
         unsafe {
-            self.previous = SetThreadExecutionState(esflags);
-            if self.previous == EXECUTION_STATE(0) {
+            if SetThreadExecutionState(esflags) == EXECUTION_STATE(0) {
                 return Err(WindowsError::from_win32());
             }
         }
-        
+        self.applied = Some(esflags);
+
         Ok(())
     }
 
-    pub fn activate_for(
-        &mut self, 
-        duration: u64, 
-        tx: Sender<()>, 
-        keep_screen_on: bool
-    ) {        
-        if self.activate(keep_screen_on).is_ok() {
-            thread::spawn(move || {
-                thread::sleep(Duration::from_secs(duration));
-                let _ = tx.send(());
-            });
+    pub fn deactivate(&mut self) {
+        if self.applied.take().is_some() {
+            unsafe {
+                SetThreadExecutionState(ES_CONTINUOUS);
+            }
+        }
+    }
+
+    // SetThreadExecutionState returns the state the calling thread had before the
+    // call. Only the event loop thread sets it, so anything other than the flags
+    // applied last means the state was reset behind our back.
+    pub fn reassert(&mut self, keep_screen_on: bool) -> bool {
+        let Some(applied) = self.applied else {
+            return false;
+        };
+        let esflags = self.flags(keep_screen_on);
+
+        let previous = unsafe { SetThreadExecutionState(esflags) };
+        if previous == EXECUTION_STATE(0) {
+            return false;
+        }
+        self.applied = Some(esflags);
+
+        previous != applied
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn supports_away_mode() -> bool {
+    LOCAL_MACHINE.open(POWER_REGKEY)
+        .and_then(|key| key.get_u32(AWAY_MODE_VALUE))
+        .is_ok_and(|enabled| enabled == 1)
+}
+
+#[cfg(target_os = "linux")]
+pub struct KeepAwake {
+    inhibitor: Mutex<Option<(Child, String)>>
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for KeepAwake {
+    fn drop(&mut self) {
+        self.deactivate();
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl KeepAwake {
+    pub fn new() -> io::Result<Self> {
+        Ok(KeepAwake {
+            inhibitor: Mutex::new(None)
+        })
+    }
+
+    pub fn set_away_mode(&mut self, _away_mode: bool) {}
+
+    fn what(keep_screen_on: bool) -> &'static str {
+        if keep_screen_on {
+            "sleep:idle"
+        } else {
+            "sleep"
+        }
+    }
+
+    fn is_inhibiting(&self, what: &str) -> bool {
+        self.inhibitor.lock().is_ok_and(|mut inhibitor| match inhibitor.as_mut() {
+            Some((child, current)) => current == what && matches!(child.try_wait(), Ok(None)),
+            None => false
+        })
+    }
+
+    pub fn activate(&mut self, keep_screen_on: bool) -> io::Result<()> {
+        if self.is_inhibiting(Self::what(keep_screen_on)) {
+            return Ok(());
         }
+
+        self.inhibit(keep_screen_on)
     }
-}
\ No newline at end of file
+
+    pub fn deactivate(&mut self) {
+        if let Ok(mut inhibitor) = self.inhibitor.lock() {
+            if let Some((mut child, _)) = inhibitor.take() {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+        }
+    }
+
+    fn inhibit(&self, keep_screen_on: bool) -> io::Result<()> {
+        let what = Self::what(keep_screen_on);
+        let child = Command::new("systemd-inhibit")
+            .args([
+                &format!("--what={}", what),
+                &format!("--who={}", INHIBIT_WHO),
+                &format!("--why={}", INHIBIT_REASON),
+                "--mode=block",
+                "sleep",
+                "infinity"
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let mut inhibitor = self.inhibitor.lock().map_err(|_| io::Error::other("inhibitor is poisoned"))?;
+        if let Some((mut previous, _)) = inhibitor.replace((child, what.to_string())) {
+            let _ = previous.kill();
+            let _ = previous.wait();
+        }
+
+        Ok(())
+    }
+
+    pub fn reassert(&self, keep_screen_on: bool) -> bool {
+        let what = Self::what(keep_screen_on);
+        let pid = self.inhibitor.lock()
+            .ok()
+            .and_then(|inhibitor| inhibitor.as_ref().map(|(child, _)| child.id()));
+        let is_listed = match (pid, inhibitor_list()) {
+            (Some(pid), Some(list)) => is_listed(&list, pid, what),
+            _ => true
+        };
+
+        if self.is_inhibiting(what) && is_listed {
+            return false;
+        }
+
+        self.inhibit(keep_screen_on).is_ok()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn inhibitor_list() -> Option<String> {
+    let output = Command::new("systemd-inhibit")
+        .args(["--list", "--no-pager", "--no-legend"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(any(test, target_os = "linux"))]
+fn is_listed(list: &str, pid: u32, what: &str) -> bool {
+    let pid = pid.to_string();
+
+    list.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .any(|fields| fields.len() > 5 && fields[0] == INHIBIT_WHO && fields[3] == pid && fields[5] == what)
+}
+
+#[cfg(target_os = "linux")]
+pub fn supports_away_mode() -> bool {
+    false
+}
+
+pub struct Timers<F> {
+    deadlines: HashMap<Hold, (SystemTime, u64)>,
+    generation: u64,
+    wake: F
+}
+
+impl<F: Fn(Hold, u64) + Clone + Send + 'static> Timers<F> {
+    pub fn new(wake: F) -> Self {
+        Self {
+            deadlines: HashMap::new(),
+            generation: 0,
+            wake
+        }
+    }
+
+    pub fn start(&mut self, hold: Hold, duration: Duration) {
+        self.arm(hold, SystemTime::now() + duration, duration);
+    }
+
+    fn arm(&mut self, hold: Hold, deadline: SystemTime, remaining: Duration) {
+        self.generation += 1;
+        self.deadlines.insert(hold, (deadline, self.generation));
+
+        let generation = self.generation;
+        let wake = self.wake.clone();
+        thread::spawn(move || {
+            thread::sleep(remaining);
+            wake(hold, generation);
+        });
+    }
+
+    pub fn cancel(&mut self, hold: Hold) {
+        self.deadlines.remove(&hold);
+    }
+
+    pub fn resume(&mut self) -> Vec<Hold> {
+        let now = SystemTime::now();
+        let mut expired = Vec::new();
+
+        for (hold, (deadline, _)) in self.deadlines.clone() {
+            match deadline.duration_since(now) {
+                Ok(remaining) => self.arm(hold, deadline, remaining),
+                Err(_) => {
+                    self.deadlines.remove(&hold);
+                    expired.push(hold);
+                }
+            }
+        }
+
+        expired
+    }
+
+    pub fn fire(&mut self, hold: Hold, generation: u64) -> bool {
+        let Some(&(deadline, current)) = self.deadlines.get(&hold) else {
+            return false;
+        };
+        if generation != current {
+            return false;
+        }
+
+        match deadline.duration_since(SystemTime::now()) {
+            Ok(remaining) if !remaining.is_zero() => {
+                self.arm(hold, deadline, remaining);
+                false
+            }
+            _ => {
+                self.deadlines.remove(&hold);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INHIBITORS: &str = "\
+GNOME Shell 1000 alice 2210 gnome-shell sleep Logging out before suspend delay
+kava        1000 alice 4312 systemd-inhibit sleep:idle Keeping the system awake block
+kava        1000 alice 4977 systemd-inhibit sleep Keeping the system awake block
+";
+
+    #[test]
+    fn ignores_wakeups_from_replaced_timers() {
+        let mut timers = Timers::new(|_, _| {});
+
+        timers.start(Hold::Timer, Duration::ZERO);
+        let stale = timers.generation;
+        timers.start(Hold::Timer, Duration::ZERO);
+
+        assert!(!timers.fire(Hold::Timer, stale));
+        assert!(timers.fire(Hold::Timer, timers.generation));
+        assert!(!timers.fire(Hold::Timer, timers.generation));
+    }
+
+    #[test]
+    fn resume_rearms_pending_timers_and_reports_expired_ones() {
+        let mut timers = Timers::new(|_, _| {});
+
+        timers.start(Hold::Timer, Duration::from_secs(3600));
+        let stale = timers.generation;
+        timers.start(Hold::ScreenTimer, Duration::ZERO);
+        thread::sleep(Duration::from_millis(10));
+
+        assert_eq!(timers.resume(), vec![Hold::ScreenTimer]);
+        assert!(!timers.fire(Hold::Timer, stale));
+        assert!(!timers.fire(Hold::Timer, timers.generation));
+    }
+
+    #[test]
+    fn finds_our_inhibitor_in_the_logind_list() {
+        assert!(is_listed(INHIBITORS, 4312, "sleep:idle"));
+        assert!(is_listed(INHIBITORS, 4977, "sleep"));
+        assert!(!is_listed(INHIBITORS, 4312, "sleep"));
+        assert!(!is_listed(INHIBITORS, 2210, "sleep"));
+        assert!(!is_listed("", 4312, "sleep:idle"));
+    }
+}
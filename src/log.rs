@@ -0,0 +1,17 @@
+use std::{fs::OpenOptions, io::Write};
+use chrono::Local;
+
+use crate::paths;
+
+const LOG_NAME: &str = "kava.log";
+
+pub fn write(message: &str) {
+    let Ok(mut file) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(paths::config_file(LOG_NAME)) else {
+        return;
+    };
+
+    let _ = writeln!(file, "{} {}", Local::now().format("%Y-%m-%d %H:%M:%S"), message);
+}
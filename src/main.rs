@@ -1,6 +1,15 @@
 #![windows_subsystem = "windows"]
 
-use std::sync::mpsc::channel;
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+        Arc, Mutex
+    },
+    thread,
+    time::{Duration, Instant}
+};
 use tao::{
     event::{Event, StartCause, WindowEvent},
     event_loop::{
@@ -19,55 +28,228 @@ use tray_icon::{
     TrayIconBuilder,TrayIconEvent,
 };
 
+mod cli;
 mod helpers;
+mod holds;
+mod idle;
+mod ipc;
+mod jiggler;
 mod keepawake;
+mod log;
+mod network;
+mod parse;
+mod paths;
 mod autolaunch;
+mod calendar;
+mod debounce;
+mod foreground;
+mod power;
 mod preferences;
+mod schedule;
+mod session;
+mod triggers;
+#[cfg(target_os = "linux")]
+mod x11;
 
-use keepawake::KeepAwake;
-use preferences::{
-    Preferences, 
-    PREF_KEEP_SCREEN_ON, 
-    PREF_RUN_ACTIVATED, 
-    PREF_TOGGLE_ON_LEFT_CLICK
+use calendar::CalendarFilter;
+use debounce::Debounce;
+use holds::{Hold, Holds, Suspension, TRIGGER_HOLDS};
+use keepawake::{KeepAwake, Timers};
+use power::PowerStatus;
+use schedule::Schedule;
+use session::SessionEvent;
+use triggers::{
+    fullscreen::AppFilter,
+    network::Location,
+    rules::{Rule as WindowRule, Scope},
+    usb::UsbId
 };
+use preferences::{keys, Preferences};
+
+const REMOTE_SESSION_POLL_INTERVAL: Duration = Duration::from_secs(10);
+const POWER_POLL_INTERVAL: Duration = Duration::from_secs(30);
+const SCHEDULE_POLL_INTERVAL: Duration = Duration::from_secs(15);
+const CALENDAR_POLL_INTERVAL: Duration = Duration::from_secs(30);
+const SENTINEL_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const FULLSCREEN_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const WINDOW_RULES_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const WINDOW_RULES_DEBOUNCE: Duration = Duration::from_secs(5);
+const NETWORK_POLL_INTERVAL: Duration = Duration::from_secs(20);
+const USB_RECHECK_INTERVAL: Duration = Duration::from_secs(5);
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(10);
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(60);
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const CONFIG_RELOAD_DEBOUNCE: Duration = Duration::from_secs(1);
 
-enum UserEvent {
+pub enum UserEvent {
     TrayIconEvent(TrayIconEvent),
-    MenuEvent(MenuEvent)
+    MenuEvent(MenuEvent),
+    TriggerEvent(Hold, bool),
+    PowerEvent(PowerStatus),
+    IdleEvent(bool),
+    WatchdogEvent,
+    SessionEvent(SessionEvent),
+    PreferencesChanged,
+    PreferenceChanged(String),
+    SentinelsChanged(Vec<String>),
+    LocationChanged(Option<Location>),
+    CliRequest(cli::Invocation, Sender<cli::Output>),
+    TimerEvent(Hold, u64)
+}
+
+fn sync_keepawake(
+    holds: &Holds,
+    is_activated: bool,
+    keepawake: &mut KeepAwake,
+    tray_icon: TrayIcon,
+    theme: Theme,
+    activate_item: MenuItem
+) -> bool {
+    if holds.is_held() && keepawake.activate(holds.keep_screen_on()).is_ok() {
+        helpers::set_icon(
+            tray_icon, 
+            theme,
+            true
+        );
+
+        activate_item.set_text("Deactivate");
+        return true;
+    }
+
+    if is_activated {
+        keepawake.deactivate();
+    }
+
+    helpers::set_icon(
+        tray_icon, 
+        theme, 
+        false
+    );
+
+    activate_item.set_text("Activate");
+    false
+}
+
+fn show_status(
+    tray_menu: &Menu,
+    tray_icon: &TrayIcon,
+    problem_item: &MenuItem,
+    problem_separator: &PredefinedMenuItem,
+    preferences: &Preferences,
+    sentinels: &[String]
+) {
+    let problem = preferences.problem();
+    let is_shown = tray_menu.items()
+        .first()
+        .is_some_and(|item| item.id() == problem_item.id());
+
+    match problem {
+        Some(problem) => {
+            problem_item.set_text(problem);
+            if !is_shown {
+                let _ = tray_menu.prepend_items(&[problem_item, problem_separator]);
+            }
+        }
+        None if is_shown => {
+            let _ = tray_menu.remove(problem_item);
+            let _ = tray_menu.remove(problem_separator);
+        }
+        None => {}
+    }
+
+    let policy = preferences.policy();
+    let tooltip: Vec<String> = problem.into_iter()
+        .chain((!policy.is_empty()).then(|| policy.reason()))
+        .map(str::to_string)
+        .chain(sentinels.iter().map(|reason| format!("Held by a script: {}", reason)))
+        .collect();
+    let _ = tray_icon.set_tooltip((!tooltip.is_empty()).then(|| tooltip.join("\n")));
+}
+
+fn shown_sentinels(sentinels: &[String], is_enabled: bool) -> &[String] {
+    if is_enabled {
+        sentinels
+    } else {
+        &[]
+    }
+}
+
+fn show_diagnostics(items: &[MenuItem], preferences: &Preferences) {
+    for (item, (name, value, source)) in items.iter().zip(preferences.diagnose().unwrap_or_default()) {
+        item.set_text(format!("{} = {} ({})", name, value, source));
+    }
+}
+
+fn show_profiles(
+    submenu: &Submenu,
+    items: &mut Vec<(CheckMenuItem, String)>,
+    preferences: &Preferences
+) {
+    let names = preferences.profiles().unwrap_or_default();
+    if !items.iter().map(|(_, name)| name).eq(names.iter()) {
+        for (item, _) in items.drain(..) {
+            let _ = submenu.remove(&item);
+        }
+        for name in names {
+            let item = CheckMenuItem::new(&name, true, false, None);
+            let _ = submenu.append(&item);
+            items.push((item, name));
+        }
+    }
+
+    let active_profile = preferences.get(&keys::ACTIVE_PROFILE).unwrap_or_default();
+    for (item, name) in items.iter() {
+        item.set_checked(*name == active_profile);
+    }
 }
 
 fn toggle_keepawake(
+    holds: &mut Holds,
     is_activated: bool,
     keepawake: &mut KeepAwake,
     tray_icon: TrayIcon,
     theme: Theme,
     activate_item: MenuItem,
     keep_screen_on: bool
-) {
+) -> bool {
     if !is_activated {
-        if keepawake.activate(keep_screen_on).is_ok() {
-            helpers::set_icon(
-                tray_icon, 
-                theme,
-                true
-            );
-
-            activate_item.set_text("Deactivate");
-        }
+        holds.acquire(Hold::Manual, keep_screen_on);
     } else {
-        drop(keepawake.clone());
-        helpers::set_icon(
-            tray_icon, 
-            theme, 
-            false
-        );
+        holds.release_all();
+    }
+
+    sync_keepawake(
+        holds,
+        is_activated,
+        keepawake,
+        tray_icon,
+        theme,
+        activate_item
+    )
+}
 
-        activate_item.set_text("Activate");
+fn start_activation_timer(
+    holds: &Holds,
+    timers: &mut Timers<impl Fn(Hold, u64) + Clone + Send + 'static>,
+    preferences: &Preferences
+) {
+    let duration = preferences.policy().cap(
+        preferences.get(&keys::ACTIVATION_DURATION).unwrap_or_default()
+    );
+    timers.cancel(Hold::Manual);
+
+    if holds.is_acquired(Hold::Manual) && !duration.is_zero() {
+        timers.start(Hold::Manual, duration);
     }
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+    let _ = paths::create_config_dir();
+
     let event_loop: EventLoop<UserEvent> = EventLoopBuilder::<UserEvent>::with_user_event().build();
 
     let proxy: EventLoopProxy<UserEvent> = event_loop.create_proxy();
@@ -80,40 +262,285 @@ fn main() {
         let _ = proxy.send_event(UserEvent::MenuEvent(event));
     }));
 
-    let activate_for_submenu: Submenu = Submenu::new("Activate for", true);
-    let activate_30_min: MenuItem = MenuItem::new("30 minutes", true, None);
-    let activate_45_min: MenuItem = MenuItem::new("45 minutes", true, None);
-    let activate_1_hour: MenuItem = MenuItem::new("1 hour", true, None);
-    let activate_2_hour: MenuItem = MenuItem::new("2 hour", true, None);
+    triggers::watch(
+        Hold::RemoteSession,
+        REMOTE_SESSION_POLL_INTERVAL,
+        event_loop.create_proxy(),
+        triggers::remote_session::is_active
+    );
+
+    power::watch(
+        power::source(),
+        POWER_POLL_INTERVAL,
+        event_loop.create_proxy()
+    );
+
+    let schedule: Arc<Mutex<Schedule>> = Arc::new(Mutex::new(Schedule::default()));
+    schedule::watch(
+        schedule.clone(),
+        SCHEDULE_POLL_INTERVAL,
+        event_loop.create_proxy()
+    );
+
+    let calendar_filter: Arc<Mutex<Option<CalendarFilter>>> = Arc::new(Mutex::new(None));
+    calendar::watch(
+        calendar_filter.clone(),
+        CALENDAR_POLL_INTERVAL,
+        event_loop.create_proxy()
+    );
+
+    let sentinel_directories: Arc<Mutex<Vec<PathBuf>>> = Arc::new(Mutex::new(Vec::new()));
+    let watched_directories = sentinel_directories.clone();
+    let sentinels_proxy: EventLoopProxy<UserEvent> = event_loop.create_proxy();
+    let mut active_sentinels: Vec<String> = Vec::new();
+    triggers::watch(
+        Hold::Sentinel,
+        SENTINEL_POLL_INTERVAL,
+        event_loop.create_proxy(),
+        move || {
+            let directories = watched_directories.lock()
+                .map(|directories| directories.clone())
+                .unwrap_or_default();
+            let sentinels = triggers::sentinel::active(&directories);
+            if sentinels != active_sentinels {
+                let _ = sentinels_proxy.send_event(UserEvent::SentinelsChanged(sentinels.clone()));
+                active_sentinels = sentinels;
+            }
+            !active_sentinels.is_empty()
+        }
+    );
+
+    let fullscreen_filter: Arc<Mutex<AppFilter>> = Arc::new(Mutex::new(AppFilter::default()));
+    let app_filter = fullscreen_filter.clone();
+    triggers::watch(
+        Hold::Fullscreen,
+        FULLSCREEN_POLL_INTERVAL,
+        event_loop.create_proxy(),
+        move || {
+            let filter = app_filter.lock()
+                .map(|filter| filter.clone())
+                .unwrap_or_default();
+            triggers::fullscreen::is_active(&filter)
+        }
+    );
+
+    let window_rules: Arc<Mutex<Vec<WindowRule>>> = Arc::new(Mutex::new(Vec::new()));
+    for (hold, scope) in [
+        (Hold::WindowRuleScreen, Scope::Screen),
+        (Hold::WindowRuleSystem, Scope::System)
+    ] {
+        let rules = window_rules.clone();
+        let mut debounce = Debounce::new(WINDOW_RULES_DEBOUNCE, false);
+        triggers::watch(
+            hold,
+            WINDOW_RULES_POLL_INTERVAL,
+            event_loop.create_proxy(),
+            move || {
+                let rules = rules.lock()
+                    .map(|rules| rules.clone())
+                    .unwrap_or_default();
+                debounce.update(triggers::rules::is_active(&rules, scope), Instant::now());
+                *debounce.value()
+            }
+        );
+    }
+
+    let network_locations: Arc<Mutex<Vec<Location>>> = Arc::new(Mutex::new(Vec::new()));
+    let locations = network_locations.clone();
+    let location_proxy: EventLoopProxy<UserEvent> = event_loop.create_proxy();
+    let mut current_location: Option<String> = None;
+    triggers::watch(
+        Hold::Network,
+        NETWORK_POLL_INTERVAL,
+        event_loop.create_proxy(),
+        move || {
+            let locations = locations.lock()
+                .map(|locations| locations.clone())
+                .unwrap_or_default();
+            let location = triggers::network::matching(&locations);
+            let name = location.as_ref().map(|location| location.to_string());
+            if name != current_location {
+                let _ = location_proxy.send_event(UserEvent::LocationChanged(location));
+                current_location = name;
+            }
+            current_location.is_some()
+        }
+    );
+
+    let usb_devices: Arc<Mutex<Vec<UsbId>>> = Arc::new(Mutex::new(Vec::new()));
+    triggers::usb::watch(
+        usb_devices.clone(),
+        USB_RECHECK_INTERVAL,
+        event_loop.create_proxy()
+    );
+
+    let idle_timeout: Arc<Mutex<Duration>> = Arc::new(Mutex::new(Duration::ZERO));
+    idle::watch(
+        idle_timeout.clone(),
+        IDLE_POLL_INTERVAL,
+        event_loop.create_proxy()
+    );
+
+    let proxy: EventLoopProxy<UserEvent> = event_loop.create_proxy();
+    thread::spawn(move || {
+        loop {
+            thread::sleep(WATCHDOG_INTERVAL);
+            if proxy.send_event(UserEvent::WatchdogEvent).is_err() {
+                break;
+            }
+        }
+    });
+
+    session::watch(event_loop.create_proxy());
+
+    preferences::watcher::watch(
+        CONFIG_POLL_INTERVAL,
+        CONFIG_RELOAD_DEBOUNCE,
+        event_loop.create_proxy()
+    );
+    let preferences_proxy: EventLoopProxy<UserEvent> = event_loop.create_proxy();
+
+    ipc::serve(event_loop.create_proxy());
+
+    let jiggling: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+    let jiggle_interval: Arc<Mutex<Duration>> = Arc::new(Mutex::new(Duration::ZERO));
+    jiggler::start(jiggling.clone(), jiggle_interval.clone());
+
+    let activate_for_submenu: Submenu = Submenu::new("Keep system awake for", true);
+    let activate_for_items: [(MenuItem, Duration); 5] = [
+        (MenuItem::new("30 minutes", true, None), Duration::from_secs(30 * 60)),
+        (MenuItem::new("45 minutes", true, None), Duration::from_secs(45 * 60)),
+        (MenuItem::new("1 hour", true, None), Duration::from_secs(60 * 60)),
+        (MenuItem::new("2 hour", true, None), Duration::from_secs(2 * 60 * 60)),
+        (MenuItem::new("4 hour", true, None), Duration::from_secs(4 * 60 * 60))
+    ];
     let _ = activate_for_submenu.append_items(&[
-        &activate_30_min,
-        &activate_45_min,
+        &activate_for_items[0].0,
+        &activate_for_items[1].0,
+        &PredefinedMenuItem::separator(),
+        &activate_for_items[2].0,
+        &activate_for_items[3].0,
+        &activate_for_items[4].0
+    ]);
+
+    let screen_on_for_submenu: Submenu = Submenu::new("Keep screen on for", true);
+    let screen_on_for_items: [(MenuItem, Duration); 4] = [
+        (MenuItem::new("15 minutes", true, None), Duration::from_secs(15 * 60)),
+        (MenuItem::new("30 minutes", true, None), Duration::from_secs(30 * 60)),
+        (MenuItem::new("1 hour", true, None), Duration::from_secs(60 * 60)),
+        (MenuItem::new("2 hour", true, None), Duration::from_secs(2 * 60 * 60))
+    ];
+    let _ = screen_on_for_submenu.append_items(&[
+        &screen_on_for_items[0].0,
+        &screen_on_for_items[1].0,
         &PredefinedMenuItem::separator(),
-        &activate_1_hour,
-        &activate_2_hour
+        &screen_on_for_items[2].0,
+        &screen_on_for_items[3].0
     ]);
 
+    let profile_submenu: Submenu = Submenu::new("Profile", true);
+    let mut profile_items: Vec<(CheckMenuItem, String)> = Vec::new();
+
     let preferences_submenu: Submenu = Submenu::new("Preferences", true);
     let keep_screen_on_item = CheckMenuItem::new("Keep screen on", true, true, None);
+    let away_mode_item: CheckMenuItem = CheckMenuItem::new("Away mode", keepawake::supports_away_mode(), false, None);
+    let keep_screen_on_fullscreen_item: CheckMenuItem = CheckMenuItem::new("Keep screen on for fullscreen apps", true, true, None);
     let toggle_on_left_click_item: CheckMenuItem = CheckMenuItem::new("Toggle on left click", true, true, None);
+    let hold_during_remote_session_item: CheckMenuItem = CheckMenuItem::new("Keep awake during remote sessions", true, false, None);
+    let follow_schedule_item: CheckMenuItem = CheckMenuItem::new("Follow weekly schedule", true, true, None);
+    let follow_calendar_item: CheckMenuItem = CheckMenuItem::new("Follow calendar", true, true, None);
+    let watch_sentinel_files_item: CheckMenuItem = CheckMenuItem::new("Honor .kava-hold files", true, true, None);
+    let apply_window_rules_item: CheckMenuItem = CheckMenuItem::new("Apply window rules", true, true, None);
+    let hold_on_known_networks_item: CheckMenuItem = CheckMenuItem::new("Keep awake on known networks", true, true, None);
+    let hold_while_usb_attached_item: CheckMenuItem = CheckMenuItem::new("Keep awake while USB devices are attached", true, true, None);
+    let pause_while_locked_item: CheckMenuItem = CheckMenuItem::new("Pause while locked", true, false, None);
+    let only_on_ac_power_item: CheckMenuItem = CheckMenuItem::new("Only on AC power", true, false, None);
+    let low_battery_submenu: Submenu = Submenu::new("Stop on low battery", true);
+    let low_battery_items: [(CheckMenuItem, u64); 4] = [
+        (CheckMenuItem::new("Never", true, true, None), 0),
+        (CheckMenuItem::new("Below 10%", true, false, None), 10),
+        (CheckMenuItem::new("Below 20%", true, false, None), 20),
+        (CheckMenuItem::new("Below 30%", true, false, None), 30)
+    ];
+    let _ = low_battery_submenu.append_items(&[
+        &low_battery_items[0].0,
+        &PredefinedMenuItem::separator(),
+        &low_battery_items[1].0,
+        &low_battery_items[2].0,
+        &low_battery_items[3].0
+    ]);
+    let idle_submenu: Submenu = Submenu::new("Pause manual activation when idle", true);
+    let idle_items: [(CheckMenuItem, Duration); 4] = [
+        (CheckMenuItem::new("Never", true, true, None), Duration::ZERO),
+        (CheckMenuItem::new("After 15 minutes", true, false, None), Duration::from_secs(15 * 60)),
+        (CheckMenuItem::new("After 30 minutes", true, false, None), Duration::from_secs(30 * 60)),
+        (CheckMenuItem::new("After 1 hour", true, false, None), Duration::from_secs(60 * 60))
+    ];
+    let _ = idle_submenu.append_items(&[
+        &idle_items[0].0,
+        &PredefinedMenuItem::separator(),
+        &idle_items[1].0,
+        &idle_items[2].0,
+        &idle_items[3].0
+    ]);
+    let simulate_activity_submenu: Submenu = Submenu::new("Simulate activity", true);
+    let simulate_activity_items: [(CheckMenuItem, Duration); 4] = [
+        (CheckMenuItem::new("Off", true, true, None), Duration::ZERO),
+        (CheckMenuItem::new("Every minute", true, false, None), Duration::from_secs(60)),
+        (CheckMenuItem::new("Every 4 minutes", true, false, None), Duration::from_secs(4 * 60)),
+        (CheckMenuItem::new("Every 10 minutes", true, false, None), Duration::from_secs(10 * 60))
+    ];
+    let _ = simulate_activity_submenu.append_items(&[
+        &simulate_activity_items[0].0,
+        &PredefinedMenuItem::separator(),
+        &simulate_activity_items[1].0,
+        &simulate_activity_items[2].0,
+        &simulate_activity_items[3].0
+    ]);
     let run_activated_item: CheckMenuItem = CheckMenuItem::new("Run activated", true, true, None);
     let autolaunch_item = CheckMenuItem::new("Run at startup", true, true, None);
+    let diagnostics_submenu: Submenu = Submenu::new("Diagnostics", true);
+    let mut diagnostics_items: Vec<MenuItem> = Vec::new();
+    let reset_item: MenuItem = MenuItem::new("Reset to defaults", true, None);
     let _ = preferences_submenu.append_items(&[
         &keep_screen_on_item,
+        &away_mode_item,
+        &keep_screen_on_fullscreen_item,
+        &simulate_activity_submenu,
         &PredefinedMenuItem::separator(),
         &toggle_on_left_click_item,
         &PredefinedMenuItem::separator(),
+        &hold_during_remote_session_item,
+        &follow_schedule_item,
+        &follow_calendar_item,
+        &watch_sentinel_files_item,
+        &apply_window_rules_item,
+        &hold_on_known_networks_item,
+        &hold_while_usb_attached_item,
+        &PredefinedMenuItem::separator(),
+        &only_on_ac_power_item,
+        &low_battery_submenu,
+        &pause_while_locked_item,
+        &idle_submenu,
+        &PredefinedMenuItem::separator(),
         &run_activated_item,
-        &autolaunch_item
+        &autolaunch_item,
+        &PredefinedMenuItem::separator(),
+        &diagnostics_submenu,
+        &reset_item
     ]);
     
     let tray_menu: Menu = Menu::new();
+    let problem_item: MenuItem = MenuItem::new("", false, None);
+    let problem_separator: PredefinedMenuItem = PredefinedMenuItem::separator();
     let activate_item: MenuItem = MenuItem::new("Activate", true, None);
     let quit_item: MenuItem = MenuItem::new("Quit", true, None);
     let _ = tray_menu.append_items(&[
         &activate_item,
         &activate_for_submenu,
+        &screen_on_for_submenu,
         &PredefinedMenuItem::separator(),
+        &profile_submenu,
         &preferences_submenu,
         &PredefinedMenuItem::about(None, Some(AboutMetadata {
             name: Some(env!("CARGO_PKG_NAME").to_string()),
@@ -130,12 +557,168 @@ fn main() {
     let mut preferences: Option<Preferences> = None;
 
     let mut keepawake: Option<KeepAwake> = None;
+    let mut holds: Holds = Holds::new();
+    let mut power_status: Option<PowerStatus> = None;
+    let mut low_battery_threshold: u64 = 0;
     let mut is_activated: bool = false;
-    let (tx, rx) = channel::<()>();
+    let timer_proxy: EventLoopProxy<UserEvent> = event_loop.create_proxy();
+    let mut timers = Timers::new(move |hold, generation| {
+        let _ = timer_proxy.send_event(UserEvent::TimerEvent(hold, generation));
+    });
+    let mut is_locked: bool = false;
+    let mut sentinels: Vec<String> = Vec::new();
 
     event_loop.run(move |event, event_loop, control_flow| {
         *control_flow = ControlFlow::Wait;
 
+        let apply_preferences = |preferences: &Preferences| -> u64 {
+            if let Ok(val) = preferences.get(&keys::RUN_ACTIVATED) {
+                run_activated_item.set_checked(val);
+            }
+            if let Ok(val) = preferences.get(&keys::TOGGLE_ON_LEFT_CLICK) {
+                toggle_on_left_click_item.set_checked(val);
+            }
+            if let Ok(val) = preferences.get(&keys::KEEP_SCREEN_ON) {
+                keep_screen_on_item.set_checked(val);
+            }
+            if let Ok(val) = preferences.get(&keys::AWAY_MODE) {
+                away_mode_item.set_checked(val && keepawake::supports_away_mode());
+            }
+            if let Ok(val) = preferences.get(&keys::HOLD_DURING_REMOTE_SESSION) {
+                hold_during_remote_session_item.set_checked(val);
+            }
+            if let Ok(val) = preferences.get(&keys::FOLLOW_SCHEDULE) {
+                follow_schedule_item.set_checked(val);
+            }
+            if let Ok(val) = preferences.load_schedule() {
+                *schedule.lock().unwrap() = val;
+            }
+            if let Ok(val) = preferences.get(&keys::FOLLOW_CALENDAR) {
+                follow_calendar_item.set_checked(val);
+            }
+            if let (Ok(path), Ok(category), Ok(title)) = (
+                preferences.get(&keys::CALENDAR_PATH),
+                preferences.get(&keys::CALENDAR_CATEGORY),
+                preferences.get(&keys::CALENDAR_TITLE_PATTERN)
+            ) {
+                *calendar_filter.lock().unwrap() = CalendarFilter::new(&path, &category, &title)
+                    .unwrap_or(None);
+            }
+            if let Ok(val) = preferences.get(&keys::WATCH_SENTINEL_FILES) {
+                watch_sentinel_files_item.set_checked(val);
+            }
+            if let Ok(val) = preferences.get(&keys::SENTINEL_DIRECTORIES) {
+                *sentinel_directories.lock().unwrap() = val.iter()
+                    .filter(|line| !line.is_empty())
+                    .map(PathBuf::from)
+                    .collect();
+            }
+            if let Ok(val) = preferences.get(&keys::KEEP_SCREEN_ON_FULLSCREEN) {
+                keep_screen_on_fullscreen_item.set_checked(val);
+            }
+            if let (Ok(allow), Ok(deny)) = (
+                preferences.get(&keys::FULLSCREEN_ALLOW),
+                preferences.get(&keys::FULLSCREEN_DENY)
+            ) {
+                *fullscreen_filter.lock().unwrap() = AppFilter::parse(&allow, &deny);
+            }
+            if let Ok(val) = preferences.get(&keys::APPLY_WINDOW_RULES) {
+                apply_window_rules_item.set_checked(val);
+            }
+            if let Ok(val) = preferences.get(&keys::WINDOW_RULES) {
+                *window_rules.lock().unwrap() = triggers::rules::parse_rules(&val);
+            }
+            if let Ok(val) = preferences.get(&keys::HOLD_ON_KNOWN_NETWORKS) {
+                hold_on_known_networks_item.set_checked(val);
+            }
+            if let Ok(val) = preferences.get(&keys::NETWORK_LOCATIONS) {
+                *network_locations.lock().unwrap() = triggers::network::parse_locations(&val);
+            }
+            if let Ok(val) = preferences.get(&keys::HOLD_WHILE_USB_ATTACHED) {
+                hold_while_usb_attached_item.set_checked(val);
+            }
+            if let Ok(val) = preferences.get(&keys::USB_DEVICES) {
+                *usb_devices.lock().unwrap() = triggers::usb::parse_ids(&val);
+            }
+            if let Ok(val) = preferences.get(&keys::ONLY_ON_AC_POWER) {
+                only_on_ac_power_item.set_checked(val);
+            }
+            if let Ok(val) = preferences.get(&keys::PAUSE_WHILE_LOCKED) {
+                pause_while_locked_item.set_checked(val);
+            }
+            if let Ok(val) = preferences.get(&keys::LOW_BATTERY_THRESHOLD) {
+                for (item, threshold) in &low_battery_items {
+                    item.set_checked(*threshold == val);
+                }
+            }
+            if let Ok(val) = preferences.get(&keys::IDLE_TIMEOUT) {
+                *idle_timeout.lock().unwrap() = val;
+                for (item, timeout) in &idle_items {
+                    item.set_checked(*timeout == val);
+                }
+            }
+            if let Ok(val) = preferences.get(&keys::SIMULATE_ACTIVITY_INTERVAL) {
+                *jiggle_interval.lock().unwrap() = val;
+                for (item, interval) in &simulate_activity_items {
+                    item.set_checked(*interval == val);
+                }
+            }
+
+            let policy = preferences.policy();
+            for (item, key) in [
+                (&run_activated_item, keys::RUN_ACTIVATED.name),
+                (&toggle_on_left_click_item, keys::TOGGLE_ON_LEFT_CLICK.name),
+                (&keep_screen_on_item, keys::KEEP_SCREEN_ON.name),
+                (&hold_during_remote_session_item, keys::HOLD_DURING_REMOTE_SESSION.name),
+                (&follow_schedule_item, keys::FOLLOW_SCHEDULE.name),
+                (&follow_calendar_item, keys::FOLLOW_CALENDAR.name),
+                (&watch_sentinel_files_item, keys::WATCH_SENTINEL_FILES.name),
+                (&keep_screen_on_fullscreen_item, keys::KEEP_SCREEN_ON_FULLSCREEN.name),
+                (&apply_window_rules_item, keys::APPLY_WINDOW_RULES.name),
+                (&hold_on_known_networks_item, keys::HOLD_ON_KNOWN_NETWORKS.name),
+                (&hold_while_usb_attached_item, keys::HOLD_WHILE_USB_ATTACHED.name),
+                (&only_on_ac_power_item, keys::ONLY_ON_AC_POWER.name),
+                (&pause_while_locked_item, keys::PAUSE_WHILE_LOCKED.name)
+            ] {
+                item.set_enabled(!policy.is_locked(key));
+            }
+            away_mode_item.set_enabled(keepawake::supports_away_mode() && !policy.is_locked(keys::AWAY_MODE.name));
+            for (submenu, key) in [
+                (&low_battery_submenu, keys::LOW_BATTERY_THRESHOLD.name),
+                (&idle_submenu, keys::IDLE_TIMEOUT.name),
+                (&simulate_activity_submenu, keys::SIMULATE_ACTIVITY_INTERVAL.name)
+            ] {
+                submenu.set_enabled(!policy.is_locked(key));
+            }
+            for (item, duration) in activate_for_items.iter().chain(&screen_on_for_items) {
+                item.set_enabled(policy.permits(*duration));
+            }
+            screen_on_for_submenu.set_enabled(policy.get::<bool>(keys::KEEP_SCREEN_ON.name) != Some(false));
+
+            preferences.get(&keys::LOW_BATTERY_THRESHOLD).unwrap_or_default()
+        };
+
+        let trigger_state = |hold: Hold| -> (bool, bool) {
+            let enabled = match hold {
+                Hold::RemoteSession => hold_during_remote_session_item.is_checked(),
+                Hold::Schedule => follow_schedule_item.is_checked(),
+                Hold::Calendar => follow_calendar_item.is_checked(),
+                Hold::Sentinel => watch_sentinel_files_item.is_checked(),
+                Hold::Fullscreen => keep_screen_on_fullscreen_item.is_checked(),
+                Hold::WindowRuleScreen | Hold::WindowRuleSystem => apply_window_rules_item.is_checked(),
+                Hold::Network => hold_on_known_networks_item.is_checked(),
+                Hold::Usb => hold_while_usb_attached_item.is_checked(),
+                _ => false
+            };
+            let keep_screen_on = match hold {
+                Hold::Fullscreen | Hold::WindowRuleScreen => true,
+                Hold::WindowRuleSystem => false,
+                _ => keep_screen_on_item.is_checked()
+            };
+
+            (enabled, keep_screen_on)
+        };
+
         match event {
             Event::NewEvents(StartCause::Init) => {
                 window = Some(
@@ -157,18 +740,32 @@ fn main() {
                     is_activated
                 );
 
-                preferences = Some(Preferences::new().unwrap());
+                preferences = Some(Preferences::load());
                 let _ = preferences.as_ref().unwrap().init();
 
-                if let Ok(val) = preferences.as_ref().unwrap().load_preference(PREF_RUN_ACTIVATED) {
-                    run_activated_item.set_checked(val);
-                }
-                if let Ok(val) = preferences.as_ref().unwrap().load_preference(PREF_TOGGLE_ON_LEFT_CLICK) {
-                    toggle_on_left_click_item.set_checked(val);
-                }
-                if let Ok(val) = preferences.as_ref().unwrap().load_preference(PREF_KEEP_SCREEN_ON) {
-                    keep_screen_on_item.set_checked(val);
+                low_battery_threshold = apply_preferences(preferences.as_ref().unwrap());
+                show_status(
+                    &tray_menu,
+                    tray_icon.as_ref().unwrap(),
+                    &problem_item,
+                    &problem_separator,
+                    preferences.as_ref().unwrap(),
+                    shown_sentinels(&sentinels, watch_sentinel_files_item.is_checked())
+                );
+
+                for _ in keys::all() {
+                    let item = MenuItem::new("", false, None);
+                    let _ = diagnostics_submenu.append(&item);
+                    diagnostics_items.push(item);
                 }
+                show_diagnostics(&diagnostics_items, preferences.as_ref().unwrap());
+
+                show_profiles(&profile_submenu, &mut profile_items, preferences.as_ref().unwrap());
+
+                let proxy = preferences_proxy.clone();
+                preferences.as_ref().unwrap().subscribe(move |name, _| {
+                    let _ = proxy.send_event(UserEvent::PreferenceChanged(name.to_string()));
+                });
 
                 if autolaunch::register().is_ok() {
                     let is_enabled = autolaunch::is_enabled();
@@ -181,17 +778,31 @@ fn main() {
                 }
 
                 keepawake = Some(KeepAwake::new().unwrap());
+                keepawake.as_mut().unwrap().set_away_mode(away_mode_item.is_checked());
+                holds.set_screen_forbidden(
+                    preferences.as_ref().unwrap().policy().get::<bool>(keys::KEEP_SCREEN_ON.name) == Some(false)
+                );
+                holds.set_max_duration(preferences.as_ref().unwrap().policy().max_hold_duration);
                 if run_activated_item.is_checked() {
-                    if keepawake.as_mut().unwrap().activate(keep_screen_on_item.is_checked()).is_ok() {
-                        helpers::set_icon(
-                            tray_icon.clone().unwrap(), 
-                            window.as_ref().unwrap().theme(),
-                            true
-                        );
-
-                        activate_item.set_text("Deactivate");
-                        is_activated = true;
-                    }
+                    holds.acquire(Hold::Manual, keep_screen_on_item.is_checked());
+                    start_activation_timer(
+                        &holds,
+                        &mut timers,
+                        preferences.as_ref().unwrap()
+                    );
+                }
+                if hold_during_remote_session_item.is_checked() && holds.is_triggered(Hold::RemoteSession) {
+                    holds.acquire(Hold::RemoteSession, keep_screen_on_item.is_checked());
+                }
+                if holds.is_held() {
+                    is_activated = sync_keepawake(
+                        &holds,
+                        is_activated,
+                        keepawake.as_mut().unwrap(),
+                        tray_icon.clone().unwrap(),
+                        window.as_ref().unwrap().theme(),
+                        activate_item.clone()
+                    );
                 }
             }
 
@@ -206,7 +817,8 @@ fn main() {
                         if button == MouseButton::Left &&
                             button_state == MouseButtonState::Up &&
                             toggle_on_left_click_item.is_checked() {
-                                toggle_keepawake(
+                                is_activated = toggle_keepawake(
+                                    &mut holds,
                                     is_activated,
                                     keepawake.as_mut().unwrap(), 
                                     tray_icon.clone().unwrap(), 
@@ -214,106 +826,413 @@ fn main() {
                                     activate_item.clone(),
                                     keep_screen_on_item.is_checked()
                                 );
-                                is_activated = !is_activated;
+                                start_activation_timer(
+                                    &holds,
+                                    &mut timers,
+                                    preferences.as_ref().unwrap()
+                                );
                             }
                     },
                     _ => {},
                 }
             }
 
-            Event::UserEvent(UserEvent::MenuEvent(event)) => {
-                if event.id == activate_item.id() {
-                    toggle_keepawake(
+            Event::UserEvent(UserEvent::PowerEvent(status)) => {
+                power_status = Some(status);
+                power::apply_policy(
+                    &mut holds,
+                    power_status,
+                    only_on_ac_power_item.is_checked(),
+                    low_battery_threshold
+                );
+
+                if keepawake.is_some() {
+                    is_activated = sync_keepawake(
+                        &holds,
                         is_activated,
-                        keepawake.as_mut().unwrap(), 
-                        tray_icon.clone().unwrap(), 
-                        window.as_ref().unwrap().theme(), 
-                        activate_item.clone(),
-                        keep_screen_on_item.is_checked()
+                        keepawake.as_mut().unwrap(),
+                        tray_icon.clone().unwrap(),
+                        window.as_ref().unwrap().theme(),
+                        activate_item.clone()
                     );
-                    is_activated = !is_activated;
                 }
+            }
 
-                if event.id == activate_30_min.id() {
-                    keepawake.as_mut().unwrap().activate_for(
-                        30 * 60, 
-                        tx.clone(),
-                        keep_screen_on_item.is_checked()
+            Event::UserEvent(UserEvent::IdleEvent(idle)) => {
+                holds.set_idle(idle);
+
+                if keepawake.is_some() {
+                    is_activated = sync_keepawake(
+                        &holds,
+                        is_activated,
+                        keepawake.as_mut().unwrap(),
+                        tray_icon.clone().unwrap(),
+                        window.as_ref().unwrap().theme(),
+                        activate_item.clone()
                     );
-                    helpers::set_icon(
-                        tray_icon.clone().unwrap(), 
-                        window.as_ref().unwrap().theme(), 
-                        true
+                }
+            }
+
+            Event::UserEvent(UserEvent::WatchdogEvent) => {
+                let expired = holds.expire(Instant::now());
+                if !expired.is_empty() && keepawake.is_some() {
+                    log::write(&format!("released holds that reached the maximum duration: {:?}", expired));
+                    for hold in &expired {
+                        timers.cancel(*hold);
+                    }
+                    is_activated = sync_keepawake(
+                        &holds,
+                        is_activated,
+                        keepawake.as_mut().unwrap(),
+                        tray_icon.clone().unwrap(),
+                        window.as_ref().unwrap().theme(),
+                        activate_item.clone()
                     );
+                }
 
-                    activate_item.set_text("Deactivate");
-                    is_activated = true;
+                if is_activated && keepawake.as_mut().unwrap().reassert(holds.keep_screen_on()) {
+                    log::write("execution state was lost, re-applied keep awake flags");
                 }
+            }
 
-                if event.id == activate_45_min.id() {
-                    keepawake.as_mut().unwrap().activate_for(
-                        45 * 60, 
-                        tx.clone(),
-                        keep_screen_on_item.is_checked()
+            Event::UserEvent(UserEvent::SessionEvent(event)) => {
+                log::write(&format!("session event: {:?}", event));
+
+                match event {
+                    SessionEvent::Lock | SessionEvent::Unlock => {
+                        is_locked = event == SessionEvent::Lock;
+                        holds.set_suspended(
+                            Suspension::Locked,
+                            is_locked && pause_while_locked_item.is_checked()
+                        );
+                    }
+                    SessionEvent::Resume => {
+                        for hold in timers.resume() {
+                            holds.release(hold);
+                        }
+                    }
+                    SessionEvent::Suspend => {}
+                }
+
+                if keepawake.is_some() {
+                    is_activated = sync_keepawake(
+                        &holds,
+                        is_activated,
+                        keepawake.as_mut().unwrap(),
+                        tray_icon.clone().unwrap(),
+                        window.as_ref().unwrap().theme(),
+                        activate_item.clone()
                     );
-                    helpers::set_icon(
-                        tray_icon.clone().unwrap(), 
-                        window.as_ref().unwrap().theme(), 
-                        true
+                }
+            }
+
+            Event::UserEvent(UserEvent::PreferencesChanged) => {
+                if keepawake.is_some() {
+                    preferences.as_mut().unwrap().reload();
+                    log::write("reloaded preferences");
+
+                    show_status(
+                        &tray_menu,
+                        tray_icon.as_ref().unwrap(),
+                        &problem_item,
+                        &problem_separator,
+                        preferences.as_ref().unwrap(),
+                        shown_sentinels(&sentinels, watch_sentinel_files_item.is_checked())
                     );
 
-                    activate_item.set_text("Deactivate");
-                    is_activated = true;
+                    holds.set_screen_forbidden(
+                        preferences.as_ref().unwrap().policy().get::<bool>(keys::KEEP_SCREEN_ON.name) == Some(false)
+                    );
+                    holds.set_max_duration(preferences.as_ref().unwrap().policy().max_hold_duration);
+                    is_activated = sync_keepawake(
+                        &holds,
+                        is_activated,
+                        keepawake.as_mut().unwrap(),
+                        tray_icon.clone().unwrap(),
+                        window.as_ref().unwrap().theme(),
+                        activate_item.clone()
+                    );
                 }
+            }
 
-                if event.id == activate_1_hour.id() {
-                    keepawake.as_mut().unwrap().activate_for(
-                        1 * 60 * 60, 
-                        tx.clone(),
-                        keep_screen_on_item.is_checked()
+            Event::UserEvent(UserEvent::PreferenceChanged(name)) => {
+                if keepawake.is_some() {
+                    log::write(&format!("preference changed: {}", name));
+
+                    low_battery_threshold = apply_preferences(preferences.as_ref().unwrap());
+
+                    show_status(
+                        &tray_menu,
+                        tray_icon.as_ref().unwrap(),
+                        &problem_item,
+                        &problem_separator,
+                        preferences.as_ref().unwrap(),
+                        shown_sentinels(&sentinels, watch_sentinel_files_item.is_checked())
                     );
-                    helpers::set_icon(
-                        tray_icon.clone().unwrap(), 
-                        window.as_ref().unwrap().theme(), 
-                        true
+                    show_profiles(&profile_submenu, &mut profile_items, preferences.as_ref().unwrap());
+                    show_diagnostics(&diagnostics_items, preferences.as_ref().unwrap());
+
+                    keepawake.as_mut().unwrap().set_away_mode(away_mode_item.is_checked());
+                    holds.set_keep_screen_on(keep_screen_on_item.is_checked());
+                    holds.set_suspended(
+                        Suspension::Locked,
+                        is_locked && pause_while_locked_item.is_checked()
                     );
+                    power::apply_policy(
+                        &mut holds,
+                        power_status,
+                        only_on_ac_power_item.is_checked(),
+                        low_battery_threshold
+                    );
+                    for hold in TRIGGER_HOLDS {
+                        let (enabled, keep_screen_on) = trigger_state(hold);
+                        if enabled && holds.is_triggered(hold) {
+                            holds.acquire(hold, keep_screen_on);
+                        } else {
+                            holds.release(hold);
+                        }
+                    }
+
+                    is_activated = sync_keepawake(
+                        &holds,
+                        is_activated,
+                        keepawake.as_mut().unwrap(),
+                        tray_icon.clone().unwrap(),
+                        window.as_ref().unwrap().theme(),
+                        activate_item.clone()
+                    );
+                }
+            }
 
-                    activate_item.set_text("Deactivate");
-                    is_activated = true;
+            Event::UserEvent(UserEvent::SentinelsChanged(active)) => {
+                sentinels = active;
+                if let (Some(preferences), Some(tray_icon)) = (preferences.as_ref(), tray_icon.as_ref()) {
+                    show_status(
+                        &tray_menu,
+                        tray_icon,
+                        &problem_item,
+                        &problem_separator,
+                        preferences,
+                        shown_sentinels(&sentinels, watch_sentinel_files_item.is_checked())
+                    );
                 }
+            }
 
-                if event.id == activate_2_hour.id() {
-                    keepawake.as_mut().unwrap().activate_for(
-                        2 * 60 * 60, 
-                        tx.clone(),
-                        keep_screen_on_item.is_checked()
+            Event::UserEvent(UserEvent::LocationChanged(location)) => {
+                log::write(&format!(
+                    "network location changed: {}",
+                    location.as_ref().map_or("none", |location| location.name.as_str())
+                ));
+
+                if let (Some(preferences), Some(profile)) = (
+                    preferences.as_ref(),
+                    location.and_then(|location| location.profile)
+                ) {
+                    if preferences.get(&keys::ACTIVE_PROFILE).is_ok_and(|active| active != profile) {
+                        match preferences.apply_profile(&profile) {
+                            Ok(true) => log::write(&format!("switched to profile {}", profile)),
+                            Ok(false) => log::write(&format!("network location profile {} does not exist", profile)),
+                            Err(err) => log::write(&format!("failed to switch to profile {}: {}", profile, err))
+                        }
+                    }
+                }
+            }
+
+            Event::UserEvent(UserEvent::CliRequest(invocation, reply)) => {
+                let output = match preferences.as_ref() {
+                    Some(preferences) => {
+                        let output = cli::execute(preferences, &invocation);
+                        show_profiles(&profile_submenu, &mut profile_items, preferences);
+                        output
+                    }
+                    None => {
+                        let mut output = cli::Output::default();
+                        output.code = 1;
+                        output.stderr = "kava: still starting up, try again\n".to_string();
+                        output
+                    }
+                };
+                let _ = reply.send(output);
+            }
+
+            Event::UserEvent(UserEvent::TriggerEvent(hold, triggered)) => {
+                holds.set_triggered(hold, triggered);
+
+                let (enabled, keep_screen_on) = trigger_state(hold);
+                if triggered && enabled {
+                    holds.acquire(hold, keep_screen_on);
+                } else {
+                    holds.release(hold);
+                }
+
+                if keepawake.is_some() {
+                    is_activated = sync_keepawake(
+                        &holds,
+                        is_activated,
+                        keepawake.as_mut().unwrap(),
+                        tray_icon.clone().unwrap(),
+                        window.as_ref().unwrap().theme(),
+                        activate_item.clone()
                     );
-                    helpers::set_icon(
+                }
+            }
+
+            Event::UserEvent(UserEvent::MenuEvent(event)) => {
+                if event.id == activate_item.id() {
+                    is_activated = toggle_keepawake(
+                        &mut holds,
+                        is_activated,
+                        keepawake.as_mut().unwrap(), 
                         tray_icon.clone().unwrap(), 
                         window.as_ref().unwrap().theme(), 
-                        true
+                        activate_item.clone(),
+                        keep_screen_on_item.is_checked()
                     );
+                    start_activation_timer(
+                        &holds,
+                        &mut timers,
+                        preferences.as_ref().unwrap()
+                    );
+                }
 
-                    activate_item.set_text("Deactivate");
-                    is_activated = true;
+                if let Some((_, duration)) = activate_for_items.iter().find(|(item, _)| event.id == item.id()) {
+                    holds.acquire(Hold::Timer, keep_screen_on_item.is_checked());
+                    timers.start(Hold::Timer, *duration);
+
+                    is_activated = sync_keepawake(
+                        &holds,
+                        is_activated,
+                        keepawake.as_mut().unwrap(),
+                        tray_icon.clone().unwrap(),
+                        window.as_ref().unwrap().theme(),
+                        activate_item.clone()
+                    );
+                }
+
+                if let Some((_, duration)) = screen_on_for_items.iter().find(|(item, _)| event.id == item.id()) {
+                    holds.acquire(Hold::ScreenTimer, true);
+                    timers.start(Hold::ScreenTimer, *duration);
+
+                    is_activated = sync_keepawake(
+                        &holds,
+                        is_activated,
+                        keepawake.as_mut().unwrap(),
+                        tray_icon.clone().unwrap(),
+                        window.as_ref().unwrap().theme(),
+                        activate_item.clone()
+                    );
+                }
+
+                if let Some(name) = profile_items.iter()
+                    .find(|(item, _)| event.id == item.id())
+                    .map(|(_, name)| name.clone())
+                {
+                    if let Ok(true) = preferences.as_ref().unwrap().apply_profile(&name) {
+                        log::write(&format!("switched to profile {}", name));
+                    }
+                    show_profiles(&profile_submenu, &mut profile_items, preferences.as_ref().unwrap());
                 }
 
                 if event.id == run_activated_item.id() {
                     let _ = preferences.as_ref()
                         .unwrap()
-                        .toggle_preference(PREF_RUN_ACTIVATED);
+                        .toggle(&keys::RUN_ACTIVATED);
                 }
 
                 if event.id == toggle_on_left_click_item.id() {
                     let _ = preferences.as_ref()
                         .unwrap()
-                        .toggle_preference(PREF_TOGGLE_ON_LEFT_CLICK);
+                        .toggle(&keys::TOGGLE_ON_LEFT_CLICK);
                 }
 
                 if event.id == keep_screen_on_item.id() {
                     let _ = preferences.as_ref()
                         .unwrap()
-                        .toggle_preference(PREF_KEEP_SCREEN_ON);
+                        .toggle(&keys::KEEP_SCREEN_ON);
+                }
+
+                if event.id == away_mode_item.id() {
+                    let _ = preferences.as_ref()
+                        .unwrap()
+                        .toggle(&keys::AWAY_MODE);
+                }
+
+                if event.id == hold_during_remote_session_item.id() {
+                    let _ = preferences.as_ref()
+                        .unwrap()
+                        .toggle(&keys::HOLD_DURING_REMOTE_SESSION);
+                }
+
+                if event.id == follow_schedule_item.id() {
+                    let _ = preferences.as_ref()
+                        .unwrap()
+                        .toggle(&keys::FOLLOW_SCHEDULE);
+                }
+
+                if event.id == follow_calendar_item.id() {
+                    let _ = preferences.as_ref()
+                        .unwrap()
+                        .toggle(&keys::FOLLOW_CALENDAR);
+                }
+
+                if event.id == watch_sentinel_files_item.id() {
+                    let _ = preferences.as_ref()
+                        .unwrap()
+                        .toggle(&keys::WATCH_SENTINEL_FILES);
+                }
+
+                if event.id == keep_screen_on_fullscreen_item.id() {
+                    let _ = preferences.as_ref()
+                        .unwrap()
+                        .toggle(&keys::KEEP_SCREEN_ON_FULLSCREEN);
+                }
+
+                if event.id == apply_window_rules_item.id() {
+                    let _ = preferences.as_ref()
+                        .unwrap()
+                        .toggle(&keys::APPLY_WINDOW_RULES);
+                }
+
+                if event.id == hold_on_known_networks_item.id() {
+                    let _ = preferences.as_ref()
+                        .unwrap()
+                        .toggle(&keys::HOLD_ON_KNOWN_NETWORKS);
+                }
+
+                if event.id == hold_while_usb_attached_item.id() {
+                    let _ = preferences.as_ref()
+                        .unwrap()
+                        .toggle(&keys::HOLD_WHILE_USB_ATTACHED);
+                }
+
+                if event.id == pause_while_locked_item.id() {
+                    let _ = preferences.as_ref()
+                        .unwrap()
+                        .toggle(&keys::PAUSE_WHILE_LOCKED);
+                }
+
+                if event.id == only_on_ac_power_item.id() {
+                    let _ = preferences.as_ref()
+                        .unwrap()
+                        .toggle(&keys::ONLY_ON_AC_POWER);
+                }
+
+                if let Some((_, threshold)) = low_battery_items.iter().find(|(item, _)| event.id == item.id()) {
+                    let _ = preferences.as_ref()
+                        .unwrap()
+                        .set(&keys::LOW_BATTERY_THRESHOLD, *threshold);
+                }
+
+                if let Some((_, timeout)) = idle_items.iter().find(|(item, _)| event.id == item.id()) {
+                    let _ = preferences.as_ref()
+                        .unwrap()
+                        .set(&keys::IDLE_TIMEOUT, *timeout);
+                }
+
+                if let Some((_, interval)) = simulate_activity_items.iter().find(|(item, _)| event.id == item.id()) {
+                    let _ = preferences.as_ref()
+                        .unwrap()
+                        .set(&keys::SIMULATE_ACTIVITY_INTERVAL, *interval);
                 }
 
                 if event.id == autolaunch_item.id() {
@@ -323,32 +1242,38 @@ fn main() {
                     };
                 }
 
+                if event.id == reset_item.id() {
+                    if preferences.as_ref().unwrap().reset_all().is_ok() {
+                        log::write("reset preferences to defaults");
+                    }
+                }
+
                 if event.id == quit_item.id() {
                     tray_icon.take();
                     *control_flow = ControlFlow::Exit;
                 }
-            }
 
-            _ => {}
-        }
+                low_battery_threshold = apply_preferences(preferences.as_ref().unwrap());
+            }
 
-        if let Ok(_) = rx.try_recv() {
-            drop(keepawake.clone().unwrap());
+            Event::UserEvent(UserEvent::TimerEvent(hold, generation)) => {
+                if timers.fire(hold, generation) && keepawake.is_some() {
+                    holds.release(hold);
 
-            helpers::set_icon(
-                tray_icon.clone().unwrap(), 
-                window.as_ref().unwrap().theme(), 
-                false
-            );
+                    is_activated = sync_keepawake(
+                        &holds,
+                        is_activated,
+                        keepawake.as_mut().unwrap(),
+                        tray_icon.clone().unwrap(),
+                        window.as_ref().unwrap().theme(),
+                        activate_item.clone()
+                    );
+                }
+            }
 
-            activate_item.set_text("Activate");
-            is_activated = false;
+            _ => {}
         }
 
-        if is_activated {
-            keep_screen_on_item.set_enabled(false);
-        } else {
-            keep_screen_on_item.set_enabled(true);
-        }
+        jiggling.store(is_activated, Ordering::Relaxed);
     });
 }
\ No newline at end of file
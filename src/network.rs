@@ -0,0 +1,188 @@
+use std::net::Ipv4Addr;
+
+#[cfg(target_os = "linux")]
+use std::process::Command;
+#[cfg(target_os = "windows")]
+use std::{ffi::c_void, ptr};
+#[cfg(target_os = "windows")]
+use windows::{
+    core::GUID,
+    Win32::{
+        Foundation::HANDLE,
+        NetworkManagement::{
+            IpHelper::{
+                GetIpForwardTable, SendARP,
+                MIB_IPFORWARDROW, MIB_IPFORWARDTABLE
+            },
+            WiFi::{
+                wlan_interface_state_connected, wlan_intf_opcode_current_connection,
+                WlanCloseHandle, WlanEnumInterfaces, WlanFreeMemory, WlanOpenHandle,
+                WlanQueryInterface, WLAN_API_VERSION_2_0, WLAN_CONNECTION_ATTRIBUTES,
+                WLAN_INTERFACE_INFO_LIST
+            }
+        }
+    }
+};
+
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct NetworkInfo {
+    pub ssid: Option<String>,
+    pub gateway: Option<Ipv4Addr>,
+    pub gateway_mac: Option<String>
+}
+
+fn format_mac(bytes: &[u8]) -> String {
+    bytes.iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+pub fn normalize_mac(mac: &str) -> String {
+    mac.trim().to_ascii_lowercase().replace('-', ":")
+}
+
+#[cfg(target_os = "windows")]
+fn connected_ssid(client: HANDLE, interface: &GUID) -> Option<String> {
+    let mut size: u32 = 0;
+    let mut data: *mut c_void = ptr::null_mut();
+
+    unsafe {
+        if WlanQueryInterface(
+            client,
+            interface,
+            wlan_intf_opcode_current_connection,
+            None,
+            &mut size,
+            &mut data,
+            None
+        ) != 0 {
+            return None;
+        }
+
+        let ssid = (*(data as *const WLAN_CONNECTION_ATTRIBUTES)).wlanAssociationAttributes.dot11Ssid;
+        let len = (ssid.uSSIDLength as usize).min(ssid.ucSSID.len());
+        WlanFreeMemory(data);
+
+        Some(String::from_utf8_lossy(&ssid.ucSSID[..len]).into_owned())
+            .filter(|ssid| !ssid.is_empty())
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn ssid() -> Option<String> {
+    let mut version: u32 = 0;
+    let mut client = HANDLE::default();
+
+    unsafe {
+        if WlanOpenHandle(WLAN_API_VERSION_2_0, None, &mut version, &mut client) != 0 {
+            return None;
+        }
+
+        let mut interfaces: *mut WLAN_INTERFACE_INFO_LIST = ptr::null_mut();
+        let mut ssid = None;
+        if WlanEnumInterfaces(client, None, &mut interfaces) == 0 {
+            let list: &[_] = std::slice::from_raw_parts(
+                (*interfaces).InterfaceInfo.as_ptr(),
+                (*interfaces).dwNumberOfItems as usize
+            );
+            ssid = list.iter()
+                .filter(|interface| interface.isState == wlan_interface_state_connected)
+                .find_map(|interface| connected_ssid(client, &interface.InterfaceGuid));
+            WlanFreeMemory(interfaces as *const c_void);
+        }
+        WlanCloseHandle(client, None);
+
+        ssid
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn gateway() -> Option<Ipv4Addr> {
+    let mut size: u32 = 0;
+
+    unsafe {
+        GetIpForwardTable(None, &mut size, true);
+
+        let mut buffer: Vec<u8> = vec![0; size as usize];
+        let table = buffer.as_mut_ptr() as *mut MIB_IPFORWARDTABLE;
+        if GetIpForwardTable(Some(table), &mut size, true) != 0 {
+            return None;
+        }
+
+        let rows: &[MIB_IPFORWARDROW] = std::slice::from_raw_parts(
+            (*table).table.as_ptr(),
+            (*table).dwNumEntries as usize
+        );
+
+        rows.iter()
+            .filter(|row| row.dwForwardDest == 0 && row.dwForwardMask == 0)
+            .min_by_key(|row| row.dwForwardMetric1)
+            .map(|row| Ipv4Addr::from(row.dwForwardNextHop.to_ne_bytes()))
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn gateway_mac(gateway: Ipv4Addr) -> Option<String> {
+    let mut mac = [0u8; 8];
+    let mut len = mac.len() as u32;
+
+    unsafe {
+        if SendARP(
+            u32::from_ne_bytes(gateway.octets()),
+            0,
+            mac.as_mut_ptr() as _,
+            &mut len
+        ) != 0 {
+            return None;
+        }
+    }
+
+    Some(format_mac(&mac[..len as usize]))
+}
+
+#[cfg(target_os = "linux")]
+fn ssid() -> Option<String> {
+    let output = Command::new("nmcli")
+        .args(["-t", "-f", "ACTIVE,SSID", "device", "wifi"])
+        .output()
+        .ok()?;
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("yes:"))
+        .map(|ssid| ssid.replace("\\:", ":"))
+        .filter(|ssid| !ssid.is_empty())
+}
+
+#[cfg(target_os = "linux")]
+fn gateway() -> Option<Ipv4Addr> {
+    std::fs::read_to_string("/proc/net/route").ok()?
+        .lines()
+        .skip(1)
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .filter(|fields| fields.len() > 7 && fields[1] == "00000000" && fields[7] == "00000000")
+        .find_map(|fields| u32::from_str_radix(fields[2], 16).ok())
+        .map(|gateway| Ipv4Addr::from(gateway.to_le_bytes()))
+}
+
+#[cfg(target_os = "linux")]
+fn gateway_mac(gateway: Ipv4Addr) -> Option<String> {
+    std::fs::read_to_string("/proc/net/arp").ok()?
+        .lines()
+        .skip(1)
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .find(|fields| fields.len() > 3 && fields[0] == gateway.to_string())
+        .map(|fields| normalize_mac(fields[3]))
+        .filter(|mac| mac != &format_mac(&[0; 6]))
+}
+
+pub fn current() -> NetworkInfo {
+    let gateway = gateway();
+
+    NetworkInfo {
+        ssid: ssid(),
+        gateway,
+        gateway_mac: gateway.and_then(gateway_mac)
+    }
+}
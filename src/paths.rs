@@ -0,0 +1,112 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::OnceLock
+};
+
+const APP_DIR: &str = "kava";
+const CONFIG_DIR_ENV: &str = "KAVA_CONFIG_DIR";
+const MIGRATED_SUFFIX: &str = ".migrated";
+
+static CONFIG_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn set_config_dir(dir: PathBuf) {
+    let _ = CONFIG_DIR_OVERRIDE.set(dir);
+}
+
+#[cfg(target_os = "windows")]
+fn default_config_dir() -> Option<PathBuf> {
+    env::var_os("APPDATA").map(|appdata| PathBuf::from(appdata).join(APP_DIR))
+}
+
+#[cfg(target_os = "linux")]
+fn default_config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join(APP_DIR))
+}
+
+pub fn config_dir() -> PathBuf {
+    CONFIG_DIR_OVERRIDE.get()
+        .cloned()
+        .or_else(|| env::var_os(CONFIG_DIR_ENV).map(PathBuf::from))
+        .or_else(default_config_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+pub fn create_config_dir() -> io::Result<()> {
+    fs::create_dir_all(config_dir())
+}
+
+pub fn config_file(name: &str) -> PathBuf {
+    config_dir().join(name)
+}
+
+fn legacy_locations(name: &str) -> Vec<PathBuf> {
+    let mut locations: Vec<PathBuf> = Vec::new();
+
+    if let Ok(dir) = env::current_dir() {
+        locations.push(dir.join(name));
+    }
+    if let Some(dir) = env::current_exe().ok().as_deref().and_then(Path::parent) {
+        locations.push(dir.join(name));
+    }
+
+    locations
+}
+
+fn migrate(locations: &[PathBuf], target: &Path) -> io::Result<()> {
+    if target.exists() {
+        return Ok(());
+    }
+
+    let Some(legacy) = locations.iter().find(|path| path.is_file()) else {
+        return Ok(());
+    };
+
+    fs::copy(legacy, target)?;
+
+    let mut migrated = legacy.clone().into_os_string();
+    migrated.push(MIGRATED_SUFFIX);
+    let _ = fs::rename(legacy, migrated);
+
+    Ok(())
+}
+
+pub fn migrate_legacy_file(name: &str) -> io::Result<()> {
+    migrate(&legacy_locations(name), &config_file(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use super::*;
+
+    #[test]
+    fn marks_legacy_files_after_copying() {
+        let dir = env::temp_dir().join(format!("kava-paths-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("config")).unwrap();
+
+        let legacy = dir.join("preferences.redb");
+        let target = dir.join("config").join("preferences.redb");
+        fs::write(&legacy, "legacy").unwrap();
+
+        migrate(&[dir.join("missing.redb"), legacy.clone()], &target).unwrap();
+
+        assert_eq!(fs::read_to_string(&target).unwrap(), "legacy");
+        assert!(!legacy.exists());
+        assert!(dir.join("preferences.redb.migrated").exists());
+
+        fs::write(&legacy, "newer").unwrap();
+        migrate(std::slice::from_ref(&legacy), &target).unwrap();
+
+        assert_eq!(fs::read_to_string(&target).unwrap(), "legacy");
+        assert!(legacy.exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,281 @@
+use std::{
+    env, fs,
+    path::PathBuf,
+    thread,
+    time::Duration
+};
+use tao::event_loop::EventLoopProxy;
+
+#[cfg(target_os = "windows")]
+use windows::Win32::System::Power::{
+    GetSystemPowerStatus, SYSTEM_POWER_STATUS
+};
+
+use crate::{
+    holds::{Holds, Suspension},
+    UserEvent
+};
+
+const FAKE_POWER_SOURCE_ENV: &str = "KAVA_FAKE_POWER_SOURCE";
+
+#[cfg(target_os = "linux")]
+const POWER_SUPPLY_PATH: &str = "/sys/class/power_supply";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PowerStatus {
+    pub on_ac: bool,
+    pub battery_percent: Option<u8>
+}
+
+pub trait PowerSource: Send {
+    fn status(&self) -> Option<PowerStatus>;
+}
+
+pub struct SystemPowerSource;
+
+#[cfg(target_os = "windows")]
+impl PowerSource for SystemPowerSource {
+    fn status(&self) -> Option<PowerStatus> {
+        let mut status = SYSTEM_POWER_STATUS::default();
+
+        unsafe {
+            GetSystemPowerStatus(&mut status).ok()?;
+        }
+
+        let has_battery = status.BatteryFlag & 128 == 0 && status.BatteryLifePercent <= 100;
+
+        Some(PowerStatus {
+            on_ac: status.ACLineStatus != 0,
+            battery_percent: has_battery.then_some(status.BatteryLifePercent)
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl PowerSource for SystemPowerSource {
+    fn status(&self) -> Option<PowerStatus> {
+        let mut mains_online: Option<bool> = None;
+        let mut battery_percent: Option<u8> = None;
+
+        for entry in fs::read_dir(POWER_SUPPLY_PATH).ok()?.flatten() {
+            let path = entry.path();
+            let read = |name: &str| fs::read_to_string(path.join(name))
+                .map(|value| value.trim().to_string())
+                .unwrap_or_default();
+
+            match read("type").as_str() {
+                "Mains" | "USB" => {
+                    mains_online = Some(mains_online.unwrap_or(false) || read("online") == "1");
+                }
+                "Battery" => {
+                    if let Ok(capacity) = read("capacity").parse::<u8>() {
+                        battery_percent = Some(battery_percent.map_or(capacity, |p| p.min(capacity)));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Some(PowerStatus {
+            on_ac: mains_online.unwrap_or(battery_percent.is_none()),
+            battery_percent
+        })
+    }
+}
+
+pub struct FakePowerSource {
+    path: PathBuf
+}
+
+impl FakePowerSource {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl PowerSource for FakePowerSource {
+    fn status(&self) -> Option<PowerStatus> {
+        let contents = fs::read_to_string(&self.path).ok()?;
+        let mut status = PowerStatus {
+            on_ac: true,
+            battery_percent: None
+        };
+
+        for line in contents.lines() {
+            match line.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+                Some(("ac", value)) => status.on_ac = value == "true",
+                Some(("battery", value)) => status.battery_percent = value.parse().ok(),
+                _ => {}
+            }
+        }
+
+        Some(status)
+    }
+}
+
+pub fn source() -> Box<dyn PowerSource> {
+    match env::var_os(FAKE_POWER_SOURCE_ENV) {
+        Some(path) => Box::new(FakePowerSource::new(PathBuf::from(path))),
+        None => Box::new(SystemPowerSource)
+    }
+}
+
+pub fn watch(
+    source: Box<dyn PowerSource>,
+    interval: Duration,
+    proxy: EventLoopProxy<UserEvent>
+) {
+    thread::spawn(move || {
+        let mut last: Option<PowerStatus> = None;
+
+        loop {
+            let status = source.status();
+            if status.is_some() && status != last {
+                if proxy.send_event(UserEvent::PowerEvent(status.unwrap())).is_err() {
+                    break;
+                }
+                last = status;
+            }
+
+            thread::sleep(interval);
+        }
+    });
+}
+
+pub fn is_low_battery(status: &PowerStatus, threshold: u64) -> bool {
+    !status.on_ac &&
+        threshold > 0 &&
+        status.battery_percent.is_some_and(|percent| (percent as u64) < threshold)
+}
+
+pub fn apply_policy(
+    holds: &mut Holds,
+    status: Option<PowerStatus>,
+    only_on_ac_power: bool,
+    low_battery_threshold: u64
+) {
+    let Some(status) = status else {
+        return;
+    };
+
+    holds.set_suspended(
+        Suspension::BatteryPower,
+        only_on_ac_power && !status.on_ac
+    );
+
+    if status.on_ac || low_battery_threshold == 0 {
+        holds.set_suspended(Suspension::LowBattery, false);
+    } else if is_low_battery(&status, low_battery_threshold) {
+        holds.set_suspended(Suspension::LowBattery, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{holds::Hold, preferences::fixture::Fixture};
+
+    fn fake_source(name: &str) -> (FakePowerSource, Fixture) {
+        let fixture = Fixture::with_extension(&format!("power-{}", name), "conf");
+        (FakePowerSource::new(fixture.0.clone()), fixture)
+    }
+
+    fn set(source: &FakePowerSource, contents: &str) -> Option<PowerStatus> {
+        fs::write(&source.path, contents).unwrap();
+        source.status()
+    }
+
+    fn held() -> Holds {
+        let mut holds = Holds::new();
+        holds.acquire(Hold::Manual, false);
+        holds
+    }
+
+    fn power_cycle(source: &FakePowerSource, holds: &mut Holds, contents: &str, only_on_ac_power: bool, threshold: u64) {
+        let status = set(source, contents);
+        apply_policy(holds, status, only_on_ac_power, threshold);
+    }
+
+    #[test]
+    fn reads_fake_power_source() {
+        let (source, _fixture) = fake_source("read");
+
+        assert_eq!(source.status(), None);
+        assert_eq!(set(&source, ""), Some(PowerStatus { on_ac: true, battery_percent: None }));
+        assert_eq!(
+            set(&source, "ac = false\nbattery = 42\n"),
+            Some(PowerStatus { on_ac: false, battery_percent: Some(42) })
+        );
+        assert_eq!(
+            set(&source, "ac=true\nbattery=full\n"),
+            Some(PowerStatus { on_ac: true, battery_percent: None })
+        );
+    }
+
+    #[test]
+    fn detects_low_battery() {
+        let battery = |percent| PowerStatus { on_ac: false, battery_percent: Some(percent) };
+
+        assert!(is_low_battery(&battery(19), 20));
+        assert!(!is_low_battery(&battery(20), 20));
+        assert!(!is_low_battery(&battery(5), 0));
+        assert!(!is_low_battery(&PowerStatus { on_ac: true, battery_percent: Some(5) }, 20));
+        assert!(!is_low_battery(&PowerStatus { on_ac: false, battery_percent: None }, 20));
+    }
+
+    #[test]
+    fn suspends_on_battery_when_only_on_ac_power() {
+        let (source, _fixture) = fake_source("ac");
+        let mut holds = held();
+
+        power_cycle(&source, &mut holds, "ac=true\nbattery=80", true, 0);
+        assert!(holds.is_held());
+
+        power_cycle(&source, &mut holds, "ac=false\nbattery=80", true, 0);
+        assert!(holds.is_suspended(Suspension::BatteryPower));
+        assert!(!holds.is_held());
+
+        power_cycle(&source, &mut holds, "ac=false\nbattery=80", false, 0);
+        assert!(holds.is_held());
+    }
+
+    #[test]
+    fn suspends_below_threshold_until_back_on_ac() {
+        let (source, _fixture) = fake_source("threshold");
+        let mut holds = held();
+
+        power_cycle(&source, &mut holds, "ac=false\nbattery=50", false, 20);
+        assert!(holds.is_held());
+
+        power_cycle(&source, &mut holds, "ac=false\nbattery=15", false, 20);
+        assert!(holds.is_suspended(Suspension::LowBattery));
+
+        power_cycle(&source, &mut holds, "ac=false\nbattery=25", false, 20);
+        assert!(holds.is_suspended(Suspension::LowBattery));
+
+        power_cycle(&source, &mut holds, "ac=true\nbattery=25", false, 20);
+        assert!(!holds.is_suspended(Suspension::LowBattery));
+        assert!(holds.is_held());
+    }
+
+    #[test]
+    fn clears_low_battery_when_threshold_is_disabled() {
+        let (source, _fixture) = fake_source("disabled");
+        let mut holds = held();
+
+        power_cycle(&source, &mut holds, "ac=false\nbattery=10", false, 20);
+        assert!(holds.is_suspended(Suspension::LowBattery));
+
+        power_cycle(&source, &mut holds, "ac=false\nbattery=10", false, 0);
+        assert!(!holds.is_suspended(Suspension::LowBattery));
+    }
+
+    #[test]
+    fn ignores_unknown_power_status() {
+        let mut holds = held();
+        holds.set_suspended(Suspension::BatteryPower, true);
+
+        apply_policy(&mut holds, None, false, 20);
+        assert!(holds.is_suspended(Suspension::BatteryPower));
+    }
+}
@@ -0,0 +1,198 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::Range,
+    time::Duration
+};
+use toml_edit::{Array, DocumentMut, ImDocument, Item, Table, TomlError};
+
+use super::{keys, PreferenceValue, Value};
+use crate::schedule::{Exception, Rule};
+
+pub const FILE_NAME: &str = "kava.toml";
+
+#[derive(Debug)]
+pub struct ConfigError {
+    pub line: usize,
+    pub message: String
+}
+
+impl ConfigError {
+    pub(super) fn new(contents: &str, span: Option<Range<usize>>, message: String) -> Self {
+        Self {
+            line: line_of(contents, span),
+            message
+        }
+    }
+
+    pub(super) fn syntax(contents: &str, err: TomlError) -> Vec<Self> {
+        vec![Self::new(contents, err.span(), err.message().trim().to_string())]
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+fn line_of(contents: &str, span: Option<Range<usize>>) -> usize {
+    let start = span.map_or(0, |span| span.start.min(contents.len()));
+    contents.as_bytes()[..start].iter().filter(|&&byte| byte == b'\n').count() + 1
+}
+
+fn validate_item(name: &str, item: &str) -> Result<(), String> {
+    match name {
+        name if name == keys::SCHEDULE_RULES.name => item.parse::<Rule>()
+            .map(|_| ())
+            .map_err(|err| err.to_string()),
+        name if name == keys::SCHEDULE_EXCEPTIONS.name => {
+            let (date, label) = item.split_once(' ').unwrap_or((item, ""));
+            Exception::parse(date, label)
+                .map(|_| ())
+                .map_err(|err| err.to_string())
+        }
+        _ => Ok(())
+    }
+}
+
+fn convert(name: &str, default: &Value, item: &Item, contents: &str) -> Result<Value, ConfigError> {
+    let error = |span: Option<Range<usize>>, message: String| ConfigError::new(contents, span, message);
+    let expected = |kind: &str| error(item.span(), format!("'{}' must be {}", name, kind));
+
+    match default {
+        Value::Bool(_) => item.as_bool()
+            .map(Value::Bool)
+            .ok_or_else(|| expected("true or false")),
+        Value::Integer(_) => item.as_integer()
+            .and_then(|value| u64::try_from(value).ok())
+            .map(Value::Integer)
+            .ok_or_else(|| expected("a non-negative integer")),
+        Value::Duration(_) => item.as_str()
+            .and_then(<Duration as PreferenceValue>::parse)
+            .or_else(|| item.as_integer()
+                .and_then(|value| u64::try_from(value).ok())
+                .map(Duration::from_secs))
+            .map(Value::Duration)
+            .ok_or_else(|| expected("a duration such as \"30s\", \"15m\" or \"2h\"")),
+        Value::String(_) => item.as_str()
+            .map(|value| Value::String(value.to_string()))
+            .ok_or_else(|| expected("a string")),
+        Value::List(_) => {
+            let array = item.as_array().ok_or_else(|| expected("an array of strings"))?;
+            let mut values = Vec::new();
+
+            for value in array.iter() {
+                let text = value.as_str()
+                    .ok_or_else(|| error(value.span(), format!("'{}' must only contain strings", name)))?;
+                validate_item(name, text)
+                    .map_err(|message| error(value.span(), message))?;
+
+                values.push(text.to_string());
+            }
+
+            Ok(Value::List(values))
+        }
+    }
+}
+
+pub fn parse(contents: &str) -> Result<HashMap<String, Value>, Vec<ConfigError>> {
+    let document = ImDocument::parse(contents).map_err(|err| ConfigError::syntax(contents, err))?;
+
+    parse_table(contents, document.as_table())
+}
+
+pub(super) fn parse_table(contents: &str, table: &Table) -> Result<HashMap<String, Value>, Vec<ConfigError>> {
+    let defaults = keys::all();
+
+    let mut values = HashMap::new();
+    let mut errors = Vec::new();
+
+    for (name, item) in table.iter() {
+        let Some((_, default)) = defaults.iter().find(|(key, _)| *key == name) else {
+            errors.push(ConfigError::new(
+                contents,
+                table.key(name).and_then(|key| key.span()),
+                format!("unknown setting '{}'", name)
+            ));
+            continue;
+        };
+
+        match convert(name, default, item, contents) {
+            Ok(value) => {
+                values.insert(name.to_string(), value);
+            }
+            Err(err) => errors.push(err)
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(values)
+    } else {
+        Err(errors)
+    }
+}
+
+pub fn render(values: &[(&str, Value)]) -> String {
+    let mut document = DocumentMut::new();
+
+    for (name, value) in values {
+        document[*name] = match value {
+            Value::Bool(value) => toml_edit::value(*value),
+            Value::Integer(value) => toml_edit::value(i64::try_from(*value).unwrap_or(i64::MAX)),
+            Value::Duration(_) => toml_edit::value(value.to_string()),
+            Value::String(value) => toml_edit::value(value.as_str()),
+            Value::List(values) => toml_edit::value(values.iter().map(String::as_str).collect::<Array>())
+        };
+    }
+
+    document.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_every_setting() {
+        let values: Vec<(&str, Value)> = vec![
+            (keys::KEEP_SCREEN_ON.name, Value::Bool(false)),
+            (keys::LOW_BATTERY_THRESHOLD.name, Value::Integer(20)),
+            (keys::IDLE_TIMEOUT.name, Value::Duration(Duration::from_secs(15 * 60))),
+            (keys::CALENDAR_CATEGORY.name, Value::String("Focus".to_string())),
+            (keys::SCHEDULE_RULES.name, Value::List(vec!["mon-fri 08:30-18:00".to_string()]))
+        ];
+
+        let parsed = parse(&render(&values)).unwrap();
+
+        assert_eq!(parsed.len(), values.len());
+        for (name, value) in &values {
+            assert_eq!(parsed.get(*name), Some(value));
+        }
+    }
+
+    #[test]
+    fn reports_line_numbers() {
+        let contents = "keep_screen_on = true\n\nidle_timeout = \"soon\"\nno_such_setting = 1\n";
+        let errors = parse(contents).unwrap_err();
+
+        let lines: Vec<usize> = errors.iter().map(|err| err.line).collect();
+        assert_eq!(lines, vec![3, 4]);
+    }
+
+    #[test]
+    fn validates_schedule_entries() {
+        let contents = "schedule_rules = [\n    \"mon-fri 08:30-18:00\",\n    \"someday 25:00-26:00\"\n]\n";
+        let errors = parse(contents).unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 3);
+    }
+
+    #[test]
+    fn reports_syntax_errors() {
+        let errors = parse("keep_screen_on = true\nrun_activated = \n").unwrap_err();
+
+        assert_eq!(errors[0].line, 2);
+    }
+}
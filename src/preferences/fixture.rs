@@ -0,0 +1,23 @@
+use std::{env, fs, path::PathBuf, process};
+
+pub struct Fixture(pub PathBuf);
+
+impl Fixture {
+    pub fn new(name: &str) -> Self {
+        Self::with_extension(name, "redb")
+    }
+
+    pub fn with_extension(name: &str, extension: &str) -> Self {
+        let path = env::temp_dir()
+            .join(format!("kava-{}-{}.{}", name, process::id(), extension));
+        let _ = fs::remove_file(&path);
+
+        Fixture(path)
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
@@ -0,0 +1,223 @@
+use redb::{
+    Database, ReadableTable, ReadableTableMetadata, TableDefinition,
+    TableHandle, WriteTransaction, Error as DBError
+};
+
+use super::{
+    keys,
+    profiles::{self, PROFILES_TABLE},
+    Value, VALUES_TABLE
+};
+use crate::log;
+
+const SCHEMA_TABLE: TableDefinition<&str, u64> = TableDefinition::new("schema");
+const SCHEMA_VERSION_KEY: &str = "version";
+
+const LEGACY_TABLE: TableDefinition<&str, bool> = TableDefinition::new("preferences");
+
+struct Migration {
+    version: u64,
+    description: &'static str,
+    apply: fn(&WriteTransaction) -> Result<(), DBError>
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "import the bool preferences table",
+        apply: import_legacy_table
+    },
+    Migration {
+        version: 2,
+        description: "rename activate_on_left_click to toggle_on_left_click",
+        apply: rename_toggle_on_left_click
+    },
+    Migration {
+        version: 3,
+        description: "add the built-in Work, Presentation and Overnight render profiles",
+        apply: add_builtin_profiles
+    }
+];
+
+fn schema_version(db: &Database) -> Result<u64, DBError> {
+    let txn = db.begin_write()?;
+    let version = {
+        let table = txn.open_table(SCHEMA_TABLE)?;
+        let version = table.get(SCHEMA_VERSION_KEY)?.map(|version| version.value());
+        version.unwrap_or(0)
+    };
+    txn.commit()?;
+
+    Ok(version)
+}
+
+pub fn run(db: &Database) -> Result<(), DBError> {
+    let current = schema_version(db)?;
+
+    for migration in MIGRATIONS.iter().filter(|migration| migration.version > current) {
+        let txn = db.begin_write()?;
+        (migration.apply)(&txn)?;
+        txn.open_table(SCHEMA_TABLE)?.insert(SCHEMA_VERSION_KEY, migration.version)?;
+        txn.commit()?;
+
+        log::write(&format!(
+            "migrated preferences to schema {}: {}",
+            migration.version,
+            migration.description
+        ));
+    }
+
+    Ok(())
+}
+
+fn table_exists(txn: &WriteTransaction, name: &str) -> Result<bool, DBError> {
+    Ok(txn.list_tables()?.any(|table| table.name() == name))
+}
+
+fn rename_key(txn: &WriteTransaction, from: &str, to: &str) -> Result<(), DBError> {
+    let mut values = txn.open_table(VALUES_TABLE)?;
+    let Some(value) = values.remove(from)?.map(|value| value.value().to_vec()) else {
+        return Ok(());
+    };
+
+    if values.get(to)?.is_none() {
+        values.insert(to, value.as_slice())?;
+    }
+
+    Ok(())
+}
+
+fn import_legacy_table(txn: &WriteTransaction) -> Result<(), DBError> {
+    if !table_exists(txn, LEGACY_TABLE.name())? {
+        return Ok(());
+    }
+
+    {
+        let legacy = txn.open_table(LEGACY_TABLE)?;
+        let mut values = txn.open_table(VALUES_TABLE)?;
+        for entry in legacy.iter()? {
+            let (name, value) = entry?;
+            if values.get(name.value())?.is_none() {
+                values.insert(name.value(), Value::Bool(value.value()).encode().as_slice())?;
+            }
+        }
+    }
+
+    txn.delete_table(LEGACY_TABLE)?;
+
+    Ok(())
+}
+
+fn rename_toggle_on_left_click(txn: &WriteTransaction) -> Result<(), DBError> {
+    rename_key(txn, "activate_on_left_click", keys::TOGGLE_ON_LEFT_CLICK.name)
+}
+
+fn add_builtin_profiles(txn: &WriteTransaction) -> Result<(), DBError> {
+    let mut table = txn.open_table(PROFILES_TABLE)?;
+
+    for (name, settings) in profiles::builtin() {
+        if table.get(name)?.is_none() {
+            table.insert(name, profiles::encode(&settings).as_slice())?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preferences::{fixture::Fixture, Preferences};
+
+    fn latest_version() -> u64 {
+        MIGRATIONS.last().map_or(0, |migration| migration.version)
+    }
+
+    fn legacy_database(fixture: &Fixture) {
+        let db = Database::create(&fixture.0).unwrap();
+        let txn = db.begin_write().unwrap();
+        {
+            let mut legacy = txn.open_table(LEGACY_TABLE).unwrap();
+            legacy.insert("run_activated", true).unwrap();
+            legacy.insert("activate_on_left_click", false).unwrap();
+            legacy.insert("keep_screen_on", false).unwrap();
+        }
+        txn.commit().unwrap();
+    }
+
+    #[test]
+    fn migrations_are_ordered() {
+        assert!(MIGRATIONS.windows(2).all(|pair| pair[0].version < pair[1].version));
+    }
+
+    #[test]
+    fn migrates_legacy_database() {
+        let fixture = Fixture::new("legacy");
+        legacy_database(&fixture);
+
+        let preferences = Preferences::load_from(&fixture.0);
+
+        assert!(preferences.get(&keys::RUN_ACTIVATED).unwrap());
+        assert!(!preferences.get(&keys::TOGGLE_ON_LEFT_CLICK).unwrap());
+        assert!(!preferences.get(&keys::KEEP_SCREEN_ON).unwrap());
+
+        assert_eq!(schema_version(&preferences.db).unwrap(), latest_version());
+        let txn = preferences.db.begin_write().unwrap();
+        assert!(!table_exists(&txn, LEGACY_TABLE.name()).unwrap());
+    }
+
+    #[test]
+    fn renames_keys_from_first_schema() {
+        let fixture = Fixture::new("schema-1");
+        {
+            let db = Database::create(&fixture.0).unwrap();
+            let txn = db.begin_write().unwrap();
+            {
+                let mut values = txn.open_table(VALUES_TABLE).unwrap();
+                values.insert("activate_on_left_click", Value::Bool(false).encode().as_slice()).unwrap();
+
+                let mut schema = txn.open_table(SCHEMA_TABLE).unwrap();
+                schema.insert(SCHEMA_VERSION_KEY, 1).unwrap();
+            }
+            txn.commit().unwrap();
+        }
+
+        let preferences = Preferences::load_from(&fixture.0);
+
+        assert!(!preferences.get(&keys::TOGGLE_ON_LEFT_CLICK).unwrap());
+        assert_eq!(preferences.load_value("activate_on_left_click").unwrap(), None);
+    }
+
+    #[test]
+    fn new_database_starts_at_latest_version() {
+        let fixture = Fixture::new("fresh");
+
+        let preferences = Preferences::load_from(&fixture.0);
+        preferences.init().unwrap();
+
+        assert_eq!(schema_version(&preferences.db).unwrap(), latest_version());
+        assert!(preferences.get(&keys::TOGGLE_ON_LEFT_CLICK).unwrap());
+    }
+
+    #[test]
+    fn migrations_do_not_overwrite_newer_values() {
+        let fixture = Fixture::new("imported");
+        legacy_database(&fixture);
+        {
+            let db = Database::create(&fixture.0).unwrap();
+            let txn = db.begin_write().unwrap();
+            {
+                let mut values = txn.open_table(VALUES_TABLE).unwrap();
+                values.insert(keys::KEEP_SCREEN_ON.name, Value::Bool(true).encode().as_slice()).unwrap();
+            }
+            txn.commit().unwrap();
+        }
+
+        let preferences = Preferences::load_from(&fixture.0);
+
+        assert!(preferences.get(&keys::KEEP_SCREEN_ON).unwrap());
+        assert!(preferences.get(&keys::RUN_ACTIVATED).unwrap());
+        let txn = preferences.db.begin_write().unwrap();
+        assert!(!table_exists(&txn, LEGACY_TABLE.name()).unwrap());
+    }
+}
@@ -0,0 +1,894 @@
+use std::{
+    collections::HashMap,
+    env, fmt, fs, io, mem,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration
+};
+use redb::{
+    backends::InMemoryBackend,
+    Builder, Database, ReadableTable, TableDefinition, Error as DBError
+};
+
+pub mod config;
+#[cfg(test)]
+pub(crate) mod fixture;
+mod migrations;
+pub mod policy;
+pub mod profiles;
+pub mod watcher;
+
+use policy::Policy;
+use crate::{
+    log,
+    paths,
+    schedule::{Exception, Rule, Schedule}
+};
+
+const DB_NAME: &str = "preferences.redb";
+const VALUES_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("values");
+const OVERRIDE_ENV_PREFIX: &str = "KAVA_PREF_";
+
+const VALUE_FORMAT_VERSION: u8 = 1;
+
+const TAG_BOOL: u8 = 0;
+const TAG_INTEGER: u8 = 1;
+const TAG_DURATION: u8 = 2;
+const TAG_STRING: u8 = 3;
+const TAG_LIST: u8 = 4;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Value {
+    Bool(bool),
+    Integer(u64),
+    Duration(Duration),
+    String(String),
+    List(Vec<String>)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Source {
+    Default,
+    Database,
+    ConfigFile,
+    Environment,
+    Policy
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::Database => write!(f, "database"),
+            Source::ConfigFile => write!(f, "{}", config::FILE_NAME),
+            Source::Environment => write!(f, "environment"),
+            Source::Policy => write!(f, "policy")
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Integer(value) => write!(f, "{}", value),
+            Value::Duration(value) => {
+                let seconds = value.as_secs();
+
+                match seconds {
+                    0 => write!(f, "0s"),
+                    _ if seconds.is_multiple_of(60 * 60) => write!(f, "{}h", seconds / (60 * 60)),
+                    _ if seconds.is_multiple_of(60) => write!(f, "{}m", seconds / 60),
+                    _ => write!(f, "{}s", seconds)
+                }
+            }
+            Value::String(value) => write!(f, "{}", value),
+            Value::List(values) => write!(f, "{}", values.join("; "))
+        }
+    }
+}
+
+impl Value {
+    fn same_kind(&self, other: &Value) -> bool {
+        mem::discriminant(self) == mem::discriminant(other)
+    }
+
+    fn parse_as(&self, text: &str) -> Option<Value> {
+        match self {
+            Value::Bool(_) => bool::parse(text).map(Value::Bool),
+            Value::Integer(_) => u64::parse(text).map(Value::Integer),
+            Value::Duration(_) => Duration::parse(text).map(Value::Duration),
+            Value::String(_) => String::parse(text).map(Value::String),
+            Value::List(_) => Vec::<String>::parse(text).map(Value::List)
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![VALUE_FORMAT_VERSION];
+
+        match self {
+            Value::Bool(value) => {
+                bytes.push(TAG_BOOL);
+                bytes.push(*value as u8);
+            }
+            Value::Integer(value) => {
+                bytes.push(TAG_INTEGER);
+                bytes.extend(value.to_le_bytes());
+            }
+            Value::Duration(value) => {
+                bytes.push(TAG_DURATION);
+                bytes.extend((value.as_millis() as u64).to_le_bytes());
+            }
+            Value::String(value) => {
+                bytes.push(TAG_STRING);
+                bytes.extend(value.as_bytes());
+            }
+            Value::List(values) => {
+                bytes.push(TAG_LIST);
+                for value in values {
+                    bytes.extend((value.len() as u32).to_le_bytes());
+                    bytes.extend(value.as_bytes());
+                }
+            }
+        }
+
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let [VALUE_FORMAT_VERSION, tag, payload @ ..] = bytes else {
+            return None;
+        };
+
+        match *tag {
+            TAG_BOOL => match payload {
+                [value] => Some(Value::Bool(*value != 0)),
+                _ => None
+            },
+            TAG_INTEGER => Some(Value::Integer(u64::from_le_bytes(payload.try_into().ok()?))),
+            TAG_DURATION => Some(Value::Duration(Duration::from_millis(
+                u64::from_le_bytes(payload.try_into().ok()?)
+            ))),
+            TAG_STRING => String::from_utf8(payload.to_vec()).ok().map(Value::String),
+            TAG_LIST => {
+                let mut values = Vec::new();
+                let mut rest = payload;
+
+                while !rest.is_empty() {
+                    let (len, tail) = rest.split_at_checked(4)?;
+                    let len = u32::from_le_bytes(len.try_into().ok()?) as usize;
+                    let (value, tail) = tail.split_at_checked(len)?;
+
+                    values.push(String::from_utf8(value.to_vec()).ok()?);
+                    rest = tail;
+                }
+
+                Some(Value::List(values))
+            }
+            _ => None
+        }
+    }
+}
+
+pub trait PreferenceValue: Clone + Sized {
+    fn into_value(self) -> Value;
+    fn from_value(value: Value) -> Option<Self>;
+    fn parse(text: &str) -> Option<Self>;
+}
+
+impl PreferenceValue for bool {
+    fn into_value(self) -> Value {
+        Value::Bool(self)
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Bool(value) => Some(value),
+            _ => None
+        }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        match text.trim().to_ascii_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Some(true),
+            "false" | "no" | "off" | "0" => Some(false),
+            _ => None
+        }
+    }
+}
+
+impl PreferenceValue for u64 {
+    fn into_value(self) -> Value {
+        Value::Integer(self)
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Integer(value) => Some(value),
+            _ => None
+        }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        text.trim().parse().ok()
+    }
+}
+
+impl PreferenceValue for Duration {
+    fn into_value(self) -> Value {
+        Value::Duration(self)
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Duration(value) => Some(value),
+            _ => None
+        }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (number, unit) = text.split_at(text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len()));
+        let number: u64 = number.parse().ok()?;
+
+        match unit.trim() {
+            "" | "s" => Some(Duration::from_secs(number)),
+            "m" => Some(Duration::from_secs(number * 60)),
+            "h" => Some(Duration::from_secs(number * 60 * 60)),
+            _ => None
+        }
+    }
+}
+
+impl PreferenceValue for String {
+    fn into_value(self) -> Value {
+        Value::String(self)
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::String(value) => Some(value),
+            _ => None
+        }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        Some(text.to_string())
+    }
+}
+
+impl PreferenceValue for Vec<String> {
+    fn into_value(self) -> Value {
+        Value::List(self)
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::List(value) => Some(value),
+            _ => None
+        }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        Some(
+            text.split(';')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect()
+        )
+    }
+}
+
+pub struct Key<T: PreferenceValue> {
+    pub name: &'static str,
+    pub default: T
+}
+
+impl<T: PreferenceValue> Key<T> {
+    pub const fn new(name: &'static str, default: T) -> Self {
+        Self { name, default }
+    }
+}
+
+pub mod keys {
+    use std::time::Duration;
+
+    use super::{Key, PreferenceValue, Value};
+
+    pub const RUN_ACTIVATED: Key<bool> = Key::new("run_activated", false);
+    pub const TOGGLE_ON_LEFT_CLICK: Key<bool> = Key::new("toggle_on_left_click", true);
+    pub const KEEP_SCREEN_ON: Key<bool> = Key::new("keep_screen_on", true);
+    pub const ACTIVATION_DURATION: Key<Duration> = Key::new("activation_duration", Duration::ZERO);
+    pub const ACTIVE_PROFILE: Key<String> = Key::new("active_profile", String::new());
+    pub const AWAY_MODE: Key<bool> = Key::new("away_mode", false);
+    pub const HOLD_DURING_REMOTE_SESSION: Key<bool> = Key::new("hold_during_remote_session", false);
+    pub const ONLY_ON_AC_POWER: Key<bool> = Key::new("only_on_ac_power", false);
+    pub const LOW_BATTERY_THRESHOLD: Key<u64> = Key::new("low_battery_threshold", 0);
+    pub const PAUSE_WHILE_LOCKED: Key<bool> = Key::new("pause_while_locked", false);
+    pub const IDLE_TIMEOUT: Key<Duration> = Key::new("idle_timeout", Duration::ZERO);
+    pub const SIMULATE_ACTIVITY_INTERVAL: Key<Duration> = Key::new("simulate_activity_interval", Duration::ZERO);
+    pub const FOLLOW_SCHEDULE: Key<bool> = Key::new("follow_schedule", true);
+    pub const SCHEDULE_RULES: Key<Vec<String>> = Key::new("schedule_rules", Vec::new());
+    pub const SCHEDULE_EXCEPTIONS: Key<Vec<String>> = Key::new("schedule_exceptions", Vec::new());
+    pub const FOLLOW_CALENDAR: Key<bool> = Key::new("follow_calendar", true);
+    pub const CALENDAR_PATH: Key<String> = Key::new("calendar_path", String::new());
+    pub const CALENDAR_CATEGORY: Key<String> = Key::new("calendar_category", String::new());
+    pub const CALENDAR_TITLE_PATTERN: Key<String> = Key::new("calendar_title_pattern", String::new());
+    pub const WATCH_SENTINEL_FILES: Key<bool> = Key::new("watch_sentinel_files", true);
+    pub const SENTINEL_DIRECTORIES: Key<Vec<String>> = Key::new("sentinel_directories", Vec::new());
+    pub const KEEP_SCREEN_ON_FULLSCREEN: Key<bool> = Key::new("keep_screen_on_fullscreen", true);
+    pub const FULLSCREEN_ALLOW: Key<Vec<String>> = Key::new("fullscreen_allow", Vec::new());
+    pub const FULLSCREEN_DENY: Key<Vec<String>> = Key::new("fullscreen_deny", Vec::new());
+    pub const APPLY_WINDOW_RULES: Key<bool> = Key::new("apply_window_rules", true);
+    pub const WINDOW_RULES: Key<Vec<String>> = Key::new("window_rules", Vec::new());
+    pub const HOLD_ON_KNOWN_NETWORKS: Key<bool> = Key::new("hold_on_known_networks", true);
+    pub const NETWORK_LOCATIONS: Key<Vec<String>> = Key::new("network_locations", Vec::new());
+    pub const HOLD_WHILE_USB_ATTACHED: Key<bool> = Key::new("hold_while_usb_attached", true);
+    pub const USB_DEVICES: Key<Vec<String>> = Key::new("usb_devices", Vec::new());
+
+    pub const PROFILE_KEYS: [&str; 16] = [
+        KEEP_SCREEN_ON.name,
+        AWAY_MODE.name,
+        ACTIVATION_DURATION.name,
+        HOLD_DURING_REMOTE_SESSION.name,
+        FOLLOW_SCHEDULE.name,
+        FOLLOW_CALENDAR.name,
+        WATCH_SENTINEL_FILES.name,
+        KEEP_SCREEN_ON_FULLSCREEN.name,
+        APPLY_WINDOW_RULES.name,
+        HOLD_ON_KNOWN_NETWORKS.name,
+        HOLD_WHILE_USB_ATTACHED.name,
+        ONLY_ON_AC_POWER.name,
+        LOW_BATTERY_THRESHOLD.name,
+        PAUSE_WHILE_LOCKED.name,
+        IDLE_TIMEOUT.name,
+        SIMULATE_ACTIVITY_INTERVAL.name
+    ];
+
+    fn entry<T: PreferenceValue>(key: &Key<T>) -> (&'static str, Value) {
+        (key.name, key.default.clone().into_value())
+    }
+
+    pub fn all() -> Vec<(&'static str, Value)> {
+        vec![
+            entry(&RUN_ACTIVATED),
+            entry(&TOGGLE_ON_LEFT_CLICK),
+            entry(&KEEP_SCREEN_ON),
+            entry(&ACTIVATION_DURATION),
+            entry(&ACTIVE_PROFILE),
+            entry(&AWAY_MODE),
+            entry(&HOLD_DURING_REMOTE_SESSION),
+            entry(&ONLY_ON_AC_POWER),
+            entry(&LOW_BATTERY_THRESHOLD),
+            entry(&PAUSE_WHILE_LOCKED),
+            entry(&IDLE_TIMEOUT),
+            entry(&SIMULATE_ACTIVITY_INTERVAL),
+            entry(&FOLLOW_SCHEDULE),
+            entry(&SCHEDULE_RULES),
+            entry(&SCHEDULE_EXCEPTIONS),
+            entry(&FOLLOW_CALENDAR),
+            entry(&CALENDAR_PATH),
+            entry(&CALENDAR_CATEGORY),
+            entry(&CALENDAR_TITLE_PATTERN),
+            entry(&WATCH_SENTINEL_FILES),
+            entry(&SENTINEL_DIRECTORIES),
+            entry(&KEEP_SCREEN_ON_FULLSCREEN),
+            entry(&FULLSCREEN_ALLOW),
+            entry(&FULLSCREEN_DENY),
+            entry(&APPLY_WINDOW_RULES),
+            entry(&WINDOW_RULES),
+            entry(&HOLD_ON_KNOWN_NETWORKS),
+            entry(&NETWORK_LOCATIONS),
+            entry(&HOLD_WHILE_USB_ATTACHED),
+            entry(&USB_DEVICES)
+        ]
+    }
+}
+
+type Subscriber = Arc<dyn Fn(&str, &Value) + Send + Sync>;
+type Snapshot = Vec<(&'static str, Value, Source)>;
+
+pub struct Preferences {
+    db: Database,
+    in_memory: bool,
+    subscribers: Mutex<Vec<Subscriber>>,
+    notified: Mutex<Option<Snapshot>>,
+    file: HashMap<String, Value>,
+    overrides: HashMap<String, String>,
+    policy: Policy,
+    problem: Option<String>,
+    config_problem: Option<String>,
+    policy_problem: Option<String>
+}
+
+fn quarantine(path: &Path) -> Option<PathBuf> {
+    let backup = path.with_extension("redb.bak");
+    fs::rename(path, &backup).ok().map(|_| backup)
+}
+
+fn is_unreadable(err: &DBError) -> bool {
+    match err {
+        DBError::Corrupted(_) |
+            DBError::UpgradeRequired(_) |
+            DBError::TableTypeMismatch { .. } |
+            DBError::TypeDefinitionChanged { .. } => true,
+        DBError::Io(err) => err.kind() == io::ErrorKind::InvalidData,
+        _ => false
+    }
+}
+
+fn in_memory() -> Result<Database, DBError> {
+    Ok(Builder::new().create_with_backend(InMemoryBackend::new())?)
+}
+
+impl Preferences {
+    pub fn load() -> Self {
+        let _ = paths::migrate_legacy_file(DB_NAME);
+
+        let mut preferences = Self::load_from(&paths::config_file(DB_NAME));
+        preferences.load_config_file();
+        preferences.load_policy();
+
+        preferences
+    }
+
+    fn load_from(path: &Path) -> Self {
+        let (db, problem) = match Self::open_database(Database::create(path).map_err(DBError::from)) {
+            Ok(db) => (Ok(db), None),
+            Err(err) if is_unreadable(&err) => {
+                log::write(&format!("failed to open {}: {}", path.display(), err));
+
+                match quarantine(path) {
+                    Some(backup) => (
+                        Self::open_database(Database::create(path).map_err(DBError::from)),
+                        Some(format!("Preferences were unreadable and have been reset, backup saved to {}", backup.display()))
+                    ),
+                    None => (Err(err), None)
+                }
+            }
+            Err(err) => (Err(err), None)
+        };
+
+        let (db, in_memory, problem) = match db {
+            Ok(db) => (db, false, problem),
+            Err(err) => {
+                let problem = match err {
+                    DBError::DatabaseAlreadyOpen => "Preferences are in use by another kava instance, changes won't be saved".to_string(),
+                    err => format!("Preferences are unavailable, changes won't be saved: {}", err)
+                };
+                (Self::open_database(in_memory()).expect("in-memory preferences"), true, Some(problem))
+            }
+        };
+        if let Some(problem) = &problem {
+            log::write(problem);
+        }
+
+        Self {
+            db,
+            in_memory,
+            subscribers: Mutex::new(Vec::new()),
+            notified: Mutex::new(None),
+            file: HashMap::new(),
+            overrides: Self::env_overrides(),
+            policy: Policy::default(),
+            problem,
+            config_problem: None,
+            policy_problem: None
+        }
+    }
+
+    fn open_database(db: Result<Database, DBError>) -> Result<Database, DBError> {
+        let db = db?;
+
+        let txn = db.begin_write()?;
+        {
+            let _values_table = txn.open_table(VALUES_TABLE)?;
+        }
+        txn.commit()?;
+
+        migrations::run(&db)?;
+
+        Ok(db)
+    }
+
+    fn env_overrides() -> HashMap<String, String> {
+        env::vars()
+            .filter_map(|(name, value)| {
+                let name = name.strip_prefix(OVERRIDE_ENV_PREFIX)?.to_ascii_lowercase();
+                Some((name, value))
+            })
+            .collect()
+    }
+
+    fn load_config_file(&mut self) {
+        self.file.clear();
+        self.config_problem = None;
+
+        let path = paths::config_file(config::FILE_NAME);
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return;
+        };
+
+        match config::parse(&contents) {
+            Ok(values) => self.file = values,
+            Err(errors) => {
+                for error in &errors {
+                    log::write(&format!("{}: {}", path.display(), error));
+                }
+                self.config_problem = Some(format!("{} was ignored, {}", path.display(), errors[0]));
+            }
+        }
+    }
+
+    fn load_policy(&mut self) {
+        match Policy::load() {
+            Ok(policy) => {
+                self.policy = policy;
+                self.policy_problem = None;
+            }
+            Err(problem) => {
+                log::write(&problem);
+                self.policy_problem = Some(format!("The administrator policy has errors and was ignored, {}", problem));
+            }
+        }
+    }
+
+    pub fn reload(&mut self) {
+        let before = self.snapshot();
+        self.load_config_file();
+        self.load_policy();
+        self.notify(before);
+    }
+
+    pub fn subscribe(&self, subscriber: impl Fn(&str, &Value) + Send + Sync + 'static) {
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(Arc::new(subscriber));
+        }
+    }
+
+    fn snapshot(&self) -> Option<Snapshot> {
+        if self.subscribers.lock().is_ok_and(|subscribers| subscribers.is_empty()) {
+            return None;
+        }
+
+        self.notified.lock().ok()
+            .and_then(|notified| notified.clone())
+            .or_else(|| self.diagnose().ok())
+    }
+
+    fn notify(&self, before: Option<Snapshot>) {
+        let (Some(before), Ok(after)) = (before, self.diagnose()) else {
+            return;
+        };
+        let Ok(subscribers) = self.subscribers.lock().map(|subscribers| subscribers.clone()) else {
+            return;
+        };
+        if let Ok(mut notified) = self.notified.lock() {
+            *notified = Some(after.clone());
+        }
+
+        for ((name, old, _), (_, new, _)) in before.iter().zip(&after) {
+            if old != new {
+                for subscriber in subscribers.iter() {
+                    subscriber(name, new);
+                }
+            }
+        }
+    }
+
+    pub fn problem(&self) -> Option<&str> {
+        self.problem.as_deref()
+            .or(self.policy_problem.as_deref())
+            .or(self.config_problem.as_deref())
+    }
+
+    pub fn is_saved(&self) -> bool {
+        !self.in_memory
+    }
+
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    pub fn init(&self) -> Result<(), DBError> {
+        self.write_defaults(false)
+    }
+
+    pub fn get<T: PreferenceValue>(&self, key: &Key<T>) -> Result<T, DBError> {
+        if let Some(value) = self.policy.get(key.name) {
+            return Ok(value);
+        }
+        if let Some(value) = self.overrides.get(key.name).and_then(|value| T::parse(value)) {
+            return Ok(value);
+        }
+        if let Some(value) = self.file.get(key.name).cloned().and_then(T::from_value) {
+            return Ok(value);
+        }
+
+        Ok(self.load_value(key.name)?
+            .and_then(T::from_value)
+            .unwrap_or_else(|| key.default.clone()))
+    }
+
+    pub fn set<T: PreferenceValue>(&self, key: &Key<T>, value: T) -> Result<(), DBError> {
+        let before = self.snapshot();
+        self.save_value(key.name, &value.into_value())?;
+        self.notify(before);
+
+        Ok(())
+    }
+
+    pub fn toggle(&self, key: &Key<bool>) -> Result<bool, DBError> {
+        let value = !self.get(key)?;
+        self.set(key, value)?;
+
+        Ok(value)
+    }
+
+    pub fn load_schedule(&self) -> Result<Schedule, DBError> {
+        let rules = self.get(&keys::SCHEDULE_RULES)?
+            .iter()
+            .filter_map(|rule| rule.parse::<Rule>().ok())
+            .collect();
+
+        let exceptions = self.get(&keys::SCHEDULE_EXCEPTIONS)?
+            .iter()
+            .filter_map(|exception| {
+                let (date, label) = exception.split_once(' ').unwrap_or((exception, ""));
+                Exception::parse(date, label).ok()
+            })
+            .collect();
+
+        Ok(Schedule { rules, exceptions })
+    }
+
+    pub fn save_schedule(&self, schedule: &Schedule) -> Result<(), DBError> {
+        self.set(
+            &keys::SCHEDULE_RULES,
+            schedule.rules.iter().map(|rule| rule.to_string()).collect()
+        )?;
+        self.set(
+            &keys::SCHEDULE_EXCEPTIONS,
+            schedule.exceptions.iter()
+                .map(|exception| format!("{} {}", exception.date_key(), exception.label).trim_end().to_string())
+                .collect()
+        )
+    }
+
+    pub fn diagnose(&self) -> Result<Vec<(&'static str, Value, Source)>, DBError> {
+        let mut entries = Vec::new();
+
+        for (name, default) in keys::all() {
+            let (value, source) = if let Some(value) = self.policy.values.get(name) {
+                (value.clone(), Source::Policy)
+            } else if let Some(value) = self.overrides.get(name).and_then(|text| default.parse_as(text)) {
+                (value, Source::Environment)
+            } else if let Some(value) = self.file.get(name) {
+                (value.clone(), Source::ConfigFile)
+            } else {
+                match self.load_value(name)?.filter(|value| value.same_kind(&default)) {
+                    Some(value) => (value, Source::Database),
+                    None => (default, Source::Default)
+                }
+            };
+
+            entries.push((name, value, source));
+        }
+
+        Ok(entries)
+    }
+
+    pub fn reset(&self, name: &str) -> Result<bool, DBError> {
+        let Some((name, default)) = keys::all().into_iter().find(|(key, _)| *key == name) else {
+            return Ok(false);
+        };
+
+        let before = self.snapshot();
+        self.save_value(name, &default)?;
+        self.notify(before);
+
+        Ok(true)
+    }
+
+    pub fn reset_all(&self) -> Result<(), DBError> {
+        let before = self.snapshot();
+        self.write_defaults(true)?;
+        self.notify(before);
+
+        Ok(())
+    }
+
+    fn write_defaults(&self, overwrite: bool) -> Result<(), DBError> {
+        let txn = self.db.begin_write()?;
+        {
+            let mut table = txn.open_table(VALUES_TABLE)?;
+            for (name, default) in keys::all() {
+                if overwrite || table.get(name)?.is_none() {
+                    table.insert(name, default.encode().as_slice())?;
+                }
+            }
+        }
+        txn.commit()?;
+
+        Ok(())
+    }
+
+    pub fn export(&self) -> Result<String, DBError> {
+        let mut values = keys::all();
+
+        for (name, value) in values.iter_mut() {
+            if let Some(stored) = self.load_value(name)?.filter(|stored| stored.same_kind(value)) {
+                *value = stored;
+            }
+        }
+
+        Ok(config::render(&values))
+    }
+
+    pub fn import(&self, values: &HashMap<String, Value>) -> Result<(), DBError> {
+        let before = self.snapshot();
+
+        let txn = self.db.begin_write()?;
+        {
+            let mut table = txn.open_table(VALUES_TABLE)?;
+            for (name, value) in values {
+                table.insert(name.as_str(), value.encode().as_slice())?;
+            }
+        }
+        txn.commit()?;
+        self.notify(before);
+
+        Ok(())
+    }
+
+    fn load_value(&self, name: &str) -> Result<Option<Value>, DBError> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(VALUES_TABLE)?;
+        let res = table.get(name)?;
+
+        Ok(res.and_then(|bytes| Value::decode(bytes.value())))
+    }
+
+    fn save_value(&self, name: &str, value: &Value) -> Result<(), DBError> {
+        let txn = self.db.begin_write()?;
+        {
+            let mut table = txn.open_table(VALUES_TABLE)?;
+            table.insert(name, value.encode().as_slice())?;
+        }
+        txn.commit()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use fixture::Fixture;
+
+    fn subscribed(name: &str) -> (Preferences, Fixture, Arc<Mutex<Vec<String>>>) {
+        let fixture = Fixture::new(name);
+
+        let preferences = Preferences::load_from(&fixture.0);
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let sink = changes.clone();
+        preferences.subscribe(move |name, _| sink.lock().unwrap().push(name.to_string()));
+
+        (preferences, fixture, changes)
+    }
+
+    #[test]
+    fn notifies_only_changed_keys() {
+        let (preferences, _fixture, changes) = subscribed("notify");
+
+        preferences.set(&keys::KEEP_SCREEN_ON, true).unwrap();
+        preferences.toggle(&keys::KEEP_SCREEN_ON).unwrap();
+        preferences.set(&keys::IDLE_TIMEOUT, Duration::from_secs(60)).unwrap();
+
+        assert_eq!(*changes.lock().unwrap(), vec![keys::KEEP_SCREEN_ON.name, keys::IDLE_TIMEOUT.name]);
+
+        drop(preferences);
+    }
+
+    #[test]
+    fn notifies_profile_switches() {
+        let (preferences, _fixture, changes) = subscribed("notify-profile");
+
+        assert!(preferences.apply_profile("Overnight render").unwrap());
+
+        let changes = changes.lock().unwrap();
+        assert!(changes.iter().any(|name| name == keys::KEEP_SCREEN_ON.name));
+        assert!(changes.iter().any(|name| name == keys::ACTIVE_PROFILE.name));
+
+        drop(preferences);
+    }
+
+    #[test]
+    fn subscribers_can_read_and_subscribe() {
+        let fixture = Fixture::new("notify-reentrant");
+        let preferences = Arc::new(Preferences::load_from(&fixture.0));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+
+        let inner = Arc::downgrade(&preferences);
+        let sink = seen.clone();
+        preferences.subscribe(move |_, _| {
+            let Some(preferences) = inner.upgrade() else {
+                return;
+            };
+            sink.lock().unwrap().push(preferences.get(&keys::KEEP_SCREEN_ON).unwrap());
+            preferences.subscribe(|_, _| {});
+        });
+
+        preferences.set(&keys::KEEP_SCREEN_ON, false).unwrap();
+
+        assert_eq!(*seen.lock().unwrap(), vec![false]);
+        assert_eq!(preferences.subscribers.lock().unwrap().len(), 2);
+
+        drop(preferences);
+    }
+
+    #[test]
+    fn diagnoses_stored_defaults_as_database() {
+        let fixture = Fixture::new("diagnose");
+        let preferences = Preferences::load_from(&fixture.0);
+
+        let source = |preferences: &Preferences| preferences.diagnose().unwrap().into_iter()
+            .find(|(name, _, _)| *name == keys::KEEP_SCREEN_ON.name)
+            .map(|(_, _, source)| source);
+
+        assert_eq!(source(&preferences), Some(Source::Default));
+
+        preferences.set(&keys::KEEP_SCREEN_ON, keys::KEEP_SCREEN_ON.default).unwrap();
+        assert_eq!(source(&preferences), Some(Source::Database));
+
+        drop(preferences);
+    }
+
+    #[test]
+    fn quarantines_unreadable_database() {
+        let fixture = Fixture::new("corrupt");
+        let backup = fixture.0.with_extension("redb.bak");
+        fs::write(&fixture.0, vec![0xab; 4096]).unwrap();
+
+        let preferences = Preferences::load_from(&fixture.0);
+
+        assert!(preferences.is_saved());
+        assert!(preferences.problem().is_some());
+        assert_eq!(fs::read(&backup).unwrap(), vec![0xab; 4096]);
+
+        drop(preferences);
+        let _ = fs::remove_file(backup);
+    }
+
+    #[test]
+    fn falls_back_to_memory_while_in_use() {
+        let fixture = Fixture::new("in-use");
+        let db = Database::create(&fixture.0).unwrap();
+
+        let preferences = Preferences::load_from(&fixture.0);
+        preferences.set(&keys::KEEP_SCREEN_ON, false).unwrap();
+
+        assert!(!preferences.is_saved());
+        assert!(preferences.problem().is_some());
+        assert!(!preferences.get(&keys::KEEP_SCREEN_ON).unwrap());
+        assert!(!fixture.0.with_extension("redb.bak").exists());
+
+        drop(db);
+    }
+
+    #[test]
+    fn keeps_database_on_io_errors() {
+        let path = std::env::temp_dir()
+            .join(format!("kava-missing-{}", std::process::id()))
+            .join(DB_NAME);
+
+        let preferences = Preferences::load_from(&path);
+
+        assert!(!preferences.is_saved());
+        assert!(preferences.problem().is_some());
+        assert!(!path.with_extension("redb.bak").exists());
+    }
+}
@@ -0,0 +1,234 @@
+use std::{collections::HashMap, time::Duration};
+
+#[cfg(target_os = "linux")]
+use std::fs;
+#[cfg(any(test, target_os = "linux"))]
+use toml_edit::ImDocument;
+
+#[cfg(target_os = "windows")]
+use windows_registry::{Key, LOCAL_MACHINE};
+
+#[cfg(any(test, target_os = "linux"))]
+use super::config::{self, ConfigError};
+#[cfg(any(test, target_os = "windows"))]
+use super::keys;
+use super::{PreferenceValue, Value};
+
+#[cfg(target_os = "linux")]
+pub const POLICY_FILE: &str = "/etc/kava/policy.toml";
+#[cfg(target_os = "windows")]
+const POLICY_REGKEY: &str = r"SOFTWARE\Policies\kava";
+
+const REASON: &str = "reason";
+const MAX_HOLD_DURATION: &str = "max_hold_duration";
+const DEFAULT_REASON: &str = "Managed by your administrator";
+
+#[cfg(any(test, target_os = "windows"))]
+enum RegistryValue {
+    Number(u32),
+    String(String),
+    List(Vec<String>)
+}
+
+#[derive(Default, PartialEq, Debug)]
+pub struct Policy {
+    pub values: HashMap<String, Value>,
+    pub max_hold_duration: Option<Duration>,
+    pub reason: Option<String>
+}
+
+impl Policy {
+    #[cfg(target_os = "linux")]
+    pub fn load() -> Result<Self, String> {
+        let Ok(contents) = fs::read_to_string(POLICY_FILE) else {
+            return Ok(Self::default());
+        };
+
+        Self::parse(&contents).map_err(|errors| format!("{}: {}", POLICY_FILE, errors[0]))
+    }
+
+    #[cfg(target_os = "windows")]
+    pub fn load() -> Result<Self, String> {
+        let Ok(key) = LOCAL_MACHINE.open(POLICY_REGKEY) else {
+            return Ok(Self::default());
+        };
+
+        let read = |key: &Key, name: &str| key.get_u32(name).map(RegistryValue::Number)
+            .or_else(|_| key.get_string(name).map(RegistryValue::String))
+            .or_else(|_| key.get_multi_string(name).map(RegistryValue::List))
+            .ok();
+
+        let values = keys::all()
+            .into_iter()
+            .map(|(name, _)| name)
+            .chain([REASON, MAX_HOLD_DURATION])
+            .filter_map(|name| read(&key, name).map(|value| (name, value)))
+            .collect();
+
+        Ok(Self::from_registry(&values))
+    }
+
+    #[cfg(any(test, target_os = "windows"))]
+    fn from_registry(values: &HashMap<&str, RegistryValue>) -> Self {
+        let duration = |value: &RegistryValue| match value {
+            RegistryValue::String(value) => <Duration as PreferenceValue>::parse(value),
+            RegistryValue::Number(secs) => Some(Duration::from_secs(*secs as u64)),
+            RegistryValue::List(_) => None
+        };
+
+        let mut policy = Self::default();
+        for (name, default) in keys::all() {
+            let Some(value) = values.get(name) else {
+                continue;
+            };
+
+            let value = match (default, value) {
+                (Value::Bool(_), RegistryValue::Number(value)) => Some(Value::Bool(*value != 0)),
+                (Value::Integer(_), RegistryValue::Number(value)) => Some(Value::Integer(*value as u64)),
+                (Value::Duration(_), value) => duration(value).map(Value::Duration),
+                (Value::String(_), RegistryValue::String(value)) => Some(Value::String(value.clone())),
+                (Value::List(_), RegistryValue::List(values)) => Some(Value::List(values.clone())),
+                (Value::List(_), RegistryValue::String(value)) => Some(Value::List(vec![value.clone()])),
+                _ => None
+            };
+
+            if let Some(value) = value {
+                policy.values.insert(name.to_string(), value);
+            }
+        }
+        if let Some(RegistryValue::String(reason)) = values.get(REASON) {
+            policy.reason = Some(reason.clone());
+        }
+        policy.max_hold_duration = values.get(MAX_HOLD_DURATION).and_then(duration);
+
+        policy
+    }
+
+    #[cfg(any(test, target_os = "linux"))]
+    fn parse(contents: &str) -> Result<Self, Vec<ConfigError>> {
+        let document = ImDocument::parse(contents).map_err(|err| ConfigError::syntax(contents, err))?;
+        let table = document.as_table();
+
+        let mut policy = Self::default();
+        let mut errors = Vec::new();
+
+        for (name, item) in table.iter() {
+            let error = |message: &str| ConfigError::new(contents, item.span(), message.to_string());
+
+            match name {
+                REASON => match item.as_str() {
+                    Some(reason) => policy.reason = Some(reason.to_string()),
+                    None => errors.push(error("'reason' must be a string"))
+                },
+                MAX_HOLD_DURATION => match item.as_str().and_then(<Duration as PreferenceValue>::parse) {
+                    Some(duration) => policy.max_hold_duration = Some(duration),
+                    None => errors.push(error("'max_hold_duration' must be a duration such as \"4h\""))
+                },
+                "settings" => match item.as_table().map(|settings| config::parse_table(contents, settings)) {
+                    Some(Ok(values)) => policy.values = values,
+                    Some(Err(mut settings_errors)) => errors.append(&mut settings_errors),
+                    None => errors.push(error("'settings' must be a table"))
+                },
+                _ => errors.push(ConfigError::new(
+                    contents,
+                    table.key(name).and_then(|key| key.span()),
+                    format!("unknown policy '{}'", name)
+                ))
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(policy)
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty() && self.max_hold_duration.is_none()
+    }
+
+    pub fn is_locked(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    pub fn get<T: PreferenceValue>(&self, name: &str) -> Option<T> {
+        self.values.get(name).cloned().and_then(T::from_value)
+    }
+
+    pub fn permits(&self, duration: Duration) -> bool {
+        self.max_hold_duration.is_none_or(|max| duration <= max)
+    }
+
+    pub fn cap(&self, duration: Duration) -> Duration {
+        match self.max_hold_duration {
+            Some(max) if duration.is_zero() || duration > max => max,
+            _ => duration
+        }
+    }
+
+    pub fn reason(&self) -> &str {
+        self.reason.as_deref().unwrap_or(DEFAULT_REASON)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_locked_settings_and_caps() {
+        let contents = "reason = \"Shared machine\"\nmax_hold_duration = \"4h\"\n\n[settings]\nkeep_screen_on = false\n";
+        let policy = Policy::parse(contents).unwrap();
+
+        assert!(policy.is_locked(keys::KEEP_SCREEN_ON.name));
+        assert_eq!(policy.get::<bool>(keys::KEEP_SCREEN_ON.name), Some(false));
+        assert_eq!(policy.reason(), "Shared machine");
+        assert_eq!(policy.cap(Duration::ZERO), Duration::from_secs(4 * 60 * 60));
+        assert_eq!(policy.cap(Duration::from_secs(60)), Duration::from_secs(60));
+        assert!(!policy.permits(Duration::from_secs(5 * 60 * 60)));
+    }
+
+    #[test]
+    fn reports_policy_errors_with_lines() {
+        let contents = "max_hold_duration = 4\n\n[settings]\nkeep_screen_on = \"no\"\n";
+        let errors = Policy::parse(contents).unwrap_err();
+
+        let lines: Vec<usize> = errors.iter().map(|err| err.line).collect();
+        assert_eq!(lines, vec![1, 4]);
+    }
+
+    #[test]
+    fn reads_registry_values() {
+        let values = HashMap::from([
+            (REASON, RegistryValue::String("Shared machine".to_string())),
+            (MAX_HOLD_DURATION, RegistryValue::Number(4 * 60 * 60)),
+            (keys::KEEP_SCREEN_ON.name, RegistryValue::Number(0)),
+            (keys::IDLE_TIMEOUT.name, RegistryValue::String("15m".to_string())),
+            (keys::SENTINEL_DIRECTORIES.name, RegistryValue::List(vec!["C:\\Builds".to_string()]))
+        ]);
+        let policy = Policy::from_registry(&values);
+
+        assert_eq!(policy.get::<bool>(keys::KEEP_SCREEN_ON.name), Some(false));
+        assert_eq!(policy.get::<Duration>(keys::IDLE_TIMEOUT.name), Some(Duration::from_secs(15 * 60)));
+        assert_eq!(
+            policy.get::<Vec<String>>(keys::SENTINEL_DIRECTORIES.name),
+            Some(vec!["C:\\Builds".to_string()])
+        );
+        assert_eq!(policy.reason(), "Shared machine");
+        assert_eq!(policy.max_hold_duration, Some(Duration::from_secs(4 * 60 * 60)));
+    }
+
+    #[test]
+    fn ignores_registry_values_of_the_wrong_type() {
+        let values = HashMap::from([
+            (REASON, RegistryValue::Number(1)),
+            (keys::KEEP_SCREEN_ON.name, RegistryValue::String("no".to_string())),
+            (keys::IDLE_TIMEOUT.name, RegistryValue::String("soon".to_string()))
+        ]);
+        let policy = Policy::from_registry(&values);
+
+        assert!(policy.is_empty());
+        assert_eq!(policy.reason(), DEFAULT_REASON);
+    }
+}
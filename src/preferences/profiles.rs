@@ -0,0 +1,195 @@
+use std::time::Duration;
+use redb::{ReadableTable, TableDefinition, Error as DBError};
+
+use super::{keys, Key, PreferenceValue, Preferences, Source, Value, VALUES_TABLE};
+
+pub const PROFILES_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("profiles");
+
+pub type Settings = Vec<(String, Value)>;
+
+fn setting<T: PreferenceValue>(key: &Key<T>, value: T) -> (String, Value) {
+    (key.name.to_string(), value.into_value())
+}
+
+pub fn builtin() -> Vec<(&'static str, Settings)> {
+    vec![
+        ("Work", vec![
+            setting(&keys::KEEP_SCREEN_ON, true),
+            setting(&keys::ACTIVATION_DURATION, Duration::ZERO),
+            setting(&keys::FOLLOW_SCHEDULE, true),
+            setting(&keys::FOLLOW_CALENDAR, true),
+            setting(&keys::HOLD_ON_KNOWN_NETWORKS, true),
+            setting(&keys::ONLY_ON_AC_POWER, false),
+            setting(&keys::LOW_BATTERY_THRESHOLD, 20),
+            setting(&keys::PAUSE_WHILE_LOCKED, true),
+            setting(&keys::IDLE_TIMEOUT, Duration::from_secs(15 * 60))
+        ]),
+        ("Presentation", vec![
+            setting(&keys::KEEP_SCREEN_ON, true),
+            setting(&keys::ACTIVATION_DURATION, Duration::from_secs(2 * 60 * 60)),
+            setting(&keys::KEEP_SCREEN_ON_FULLSCREEN, true),
+            setting(&keys::APPLY_WINDOW_RULES, true),
+            setting(&keys::ONLY_ON_AC_POWER, false),
+            setting(&keys::LOW_BATTERY_THRESHOLD, 10),
+            setting(&keys::PAUSE_WHILE_LOCKED, false),
+            setting(&keys::IDLE_TIMEOUT, Duration::ZERO)
+        ]),
+        ("Overnight render", vec![
+            setting(&keys::KEEP_SCREEN_ON, false),
+            setting(&keys::ACTIVATION_DURATION, Duration::ZERO),
+            setting(&keys::ONLY_ON_AC_POWER, true),
+            setting(&keys::LOW_BATTERY_THRESHOLD, 30),
+            setting(&keys::PAUSE_WHILE_LOCKED, false),
+            setting(&keys::IDLE_TIMEOUT, Duration::ZERO)
+        ])
+    ]
+}
+
+fn split_chunk(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (len, rest) = bytes.split_at_checked(4)?;
+    let len = u32::from_le_bytes(len.try_into().ok()?) as usize;
+
+    rest.split_at_checked(len)
+}
+
+pub fn encode(settings: &[(String, Value)]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+
+    for (name, value) in settings {
+        let value = value.encode();
+
+        bytes.extend((name.len() as u32).to_le_bytes());
+        bytes.extend(name.as_bytes());
+        bytes.extend((value.len() as u32).to_le_bytes());
+        bytes.extend(value);
+    }
+
+    bytes
+}
+
+fn decode(bytes: &[u8]) -> Option<Settings> {
+    let mut settings = Vec::new();
+    let mut rest = bytes;
+
+    while !rest.is_empty() {
+        let (name, tail) = split_chunk(rest)?;
+        let (value, tail) = split_chunk(tail)?;
+
+        settings.push((String::from_utf8(name.to_vec()).ok()?, Value::decode(value)?));
+        rest = tail;
+    }
+
+    Some(settings)
+}
+
+impl Preferences {
+    pub fn profiles(&self) -> Result<Vec<String>, DBError> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(PROFILES_TABLE)?;
+
+        let mut names = Vec::new();
+        for entry in table.iter()? {
+            let (name, _) = entry?;
+            names.push(name.value().to_string());
+        }
+
+        Ok(names)
+    }
+
+    pub fn profile(&self, name: &str) -> Result<Option<Settings>, DBError> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(PROFILES_TABLE)?;
+        let res = table.get(name)?;
+
+        Ok(res.and_then(|bytes| decode(bytes.value())))
+    }
+
+    pub fn save_profile(&self, name: &str) -> Result<(), DBError> {
+        let settings: Settings = self.diagnose()?
+            .into_iter()
+            .filter(|(name, _, source)| {
+                keys::PROFILE_KEYS.contains(name) && !matches!(source, Source::Policy | Source::Environment)
+            })
+            .map(|(name, value, _)| (name.to_string(), value))
+            .collect();
+
+        let txn = self.db.begin_write()?;
+        {
+            let mut table = txn.open_table(PROFILES_TABLE)?;
+            table.insert(name, encode(&settings).as_slice())?;
+        }
+        txn.commit()?;
+
+        Ok(())
+    }
+
+    pub fn delete_profile(&self, name: &str) -> Result<bool, DBError> {
+        let txn = self.db.begin_write()?;
+        let removed = txn.open_table(PROFILES_TABLE)?.remove(name)?.is_some();
+        txn.commit()?;
+
+        Ok(removed)
+    }
+
+    pub fn apply_profile(&self, name: &str) -> Result<bool, DBError> {
+        let Some(settings) = self.profile(name)? else {
+            return Ok(false);
+        };
+        let before = self.snapshot();
+
+        let txn = self.db.begin_write()?;
+        {
+            let mut table = txn.open_table(VALUES_TABLE)?;
+            for (name, value) in &settings {
+                table.insert(name.as_str(), value.encode().as_slice())?;
+            }
+            table.insert(
+                keys::ACTIVE_PROFILE.name,
+                Value::String(name.to_string()).encode().as_slice()
+            )?;
+        }
+        txn.commit()?;
+        self.notify(before);
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preferences::fixture::Fixture;
+
+    #[test]
+    fn new_database_has_builtin_profiles() {
+        let fixture = Fixture::new("profiles");
+
+        let preferences = Preferences::load_from(&fixture.0);
+        preferences.init().unwrap();
+
+        assert_eq!(preferences.profiles().unwrap().len(), builtin().len());
+        assert!(preferences.apply_profile("Presentation").unwrap());
+        assert_eq!(preferences.get(&keys::ACTIVATION_DURATION).unwrap(), Duration::from_secs(2 * 60 * 60));
+        assert_eq!(preferences.get(&keys::ACTIVE_PROFILE).unwrap(), "Presentation");
+        assert!(!preferences.apply_profile("Missing").unwrap());
+    }
+
+    #[test]
+    fn saves_user_values() {
+        let fixture = Fixture::new("profiles-save");
+
+        let mut preferences = Preferences::load_from(&fixture.0);
+        preferences.init().unwrap();
+        preferences.set(&keys::LOW_BATTERY_THRESHOLD, 15).unwrap();
+        preferences.file.insert(keys::IDLE_TIMEOUT.name.to_string(), Value::Duration(Duration::from_secs(5 * 60)));
+        preferences.overrides.insert(keys::ACTIVATION_DURATION.name.to_string(), "30m".to_string());
+
+        preferences.save_profile("Desk").unwrap();
+        let settings = preferences.profile("Desk").unwrap().unwrap();
+
+        assert!(settings.contains(&(keys::LOW_BATTERY_THRESHOLD.name.to_string(), Value::Integer(15))));
+        assert!(settings.contains(&(keys::IDLE_TIMEOUT.name.to_string(), Value::Duration(Duration::from_secs(5 * 60)))));
+        assert!(!settings.iter().any(|(name, _)| name == keys::ACTIVATION_DURATION.name));
+        assert_eq!(settings.len(), keys::PROFILE_KEYS.len() - 1);
+    }
+}
@@ -0,0 +1,119 @@
+use std::{
+    fs,
+    path::Path,
+    thread,
+    time::{Duration, Instant, SystemTime}
+};
+use tao::event_loop::EventLoopProxy;
+
+#[cfg(target_os = "linux")]
+use super::policy::POLICY_FILE;
+#[cfg(target_os = "windows")]
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::{CloseHandle, HANDLE, WAIT_OBJECT_0},
+        System::{
+            Registry::{
+                RegCloseKey, RegNotifyChangeKeyValue, RegOpenKeyExW, HKEY, HKEY_LOCAL_MACHINE,
+                KEY_NOTIFY, REG_NOTIFY_CHANGE_LAST_SET, REG_NOTIFY_CHANGE_NAME
+            },
+            Threading::{CreateEventW, WaitForSingleObject}
+        }
+    }
+};
+use super::{config, DB_NAME};
+use crate::{debounce::Debounce, paths, UserEvent};
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(target_os = "linux")]
+struct PolicyStamp;
+
+#[cfg(target_os = "linux")]
+impl PolicyStamp {
+    fn new() -> Self {
+        Self
+    }
+
+    fn get(&mut self) -> Option<SystemTime> {
+        modified(Path::new(POLICY_FILE))
+    }
+}
+
+#[cfg(target_os = "windows")]
+struct PolicyStamp {
+    key: HKEY,
+    event: HANDLE,
+    changes: u64
+}
+
+#[cfg(target_os = "windows")]
+impl PolicyStamp {
+    fn new() -> Self {
+        let mut key = HKEY::default();
+        let event = unsafe { CreateEventW(None, false, false, None) }.unwrap_or_default();
+        let _ = unsafe { RegOpenKeyExW(HKEY_LOCAL_MACHINE, w!(r"SOFTWARE\Policies"), None, KEY_NOTIFY, &mut key) };
+
+        let stamp = Self { key, event, changes: 0 };
+        stamp.register();
+        stamp
+    }
+
+    fn register(&self) {
+        let _ = unsafe {
+            RegNotifyChangeKeyValue(
+                self.key,
+                true,
+                REG_NOTIFY_CHANGE_NAME | REG_NOTIFY_CHANGE_LAST_SET,
+                Some(self.event),
+                true
+            )
+        };
+    }
+
+    fn get(&mut self) -> u64 {
+        if unsafe { WaitForSingleObject(self.event, 0) } == WAIT_OBJECT_0 {
+            self.changes += 1;
+            self.register();
+        }
+
+        self.changes
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for PolicyStamp {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = RegCloseKey(self.key);
+            let _ = CloseHandle(self.event);
+        }
+    }
+}
+
+pub fn watch(
+    interval: Duration,
+    delay: Duration,
+    proxy: EventLoopProxy<UserEvent>
+) {
+    let path = paths::config_file(config::FILE_NAME);
+    let db_path = paths::config_file(DB_NAME);
+
+    thread::spawn(move || {
+        let mut policy = PolicyStamp::new();
+        let mut changes = Debounce::new(delay, (modified(&path), modified(&db_path), policy.get()));
+
+        loop {
+            thread::sleep(interval);
+
+            let current = (modified(&path), modified(&db_path), policy.get());
+            if changes.update(current, Instant::now())
+                && proxy.send_event(UserEvent::PreferencesChanged).is_err() {
+                break;
+            }
+        }
+    });
+}
@@ -0,0 +1,244 @@
+use std::{
+    fmt,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration
+};
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use tao::event_loop::EventLoopProxy;
+
+use crate::{holds::Hold, parse::ParseError, UserEvent};
+
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+const TIME_FORMAT: &str = "%H:%M";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Rule {
+    days: [bool; 7],
+    start: NaiveTime,
+    end: NaiveTime
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Exception {
+    pub date: NaiveDate,
+    pub label: String
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct Schedule {
+    pub rules: Vec<Rule>,
+    pub exceptions: Vec<Exception>
+}
+
+fn parse_day(name: &str) -> Result<usize, ParseError> {
+    DAY_NAMES.iter()
+        .position(|day| day.eq_ignore_ascii_case(name))
+        .ok_or_else(|| ParseError(format!("unknown day '{}'", name)))
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime, ParseError> {
+    NaiveTime::parse_from_str(value, TIME_FORMAT)
+        .map_err(|_| ParseError(format!("invalid time '{}', expected HH:MM", value)))
+}
+
+impl Rule {
+    fn runs_on(&self, day: Weekday) -> bool {
+        self.days[day.num_days_from_monday() as usize]
+    }
+
+    pub fn is_active(&self, now: NaiveDateTime) -> bool {
+        let time = now.time();
+        let today = now.weekday();
+
+        if self.start < self.end {
+            self.runs_on(today) && time >= self.start && time < self.end
+        } else {
+            (self.runs_on(today) && time >= self.start) ||
+                (self.runs_on(today.pred()) && time < self.end)
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (days, hours) = s.trim()
+            .split_once(' ')
+            .ok_or_else(|| ParseError(format!("expected '<days> <start>-<end>', got '{}'", s)))?;
+
+        let mut rule_days = [false; 7];
+        for range in days.split(',') {
+            match range.split_once('-') {
+                Some((first, last)) => {
+                    let (first, last) = (parse_day(first)?, parse_day(last)?);
+                    let mut day = first;
+                    loop {
+                        rule_days[day] = true;
+                        if day == last {
+                            break;
+                        }
+                        day = (day + 1) % 7;
+                    }
+                }
+                None => rule_days[parse_day(range)?] = true
+            }
+        }
+
+        let (start, end) = hours.trim()
+            .split_once('-')
+            .ok_or_else(|| ParseError(format!("expected '<start>-<end>', got '{}'", hours)))?;
+
+        Ok(Rule {
+            days: rule_days,
+            start: parse_time(start)?,
+            end: parse_time(end)?
+        })
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let days: Vec<&str> = DAY_NAMES.iter()
+            .zip(self.days)
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| *name)
+            .collect();
+
+        write!(
+            f,
+            "{} {}-{}",
+            days.join(","),
+            self.start.format(TIME_FORMAT),
+            self.end.format(TIME_FORMAT)
+        )
+    }
+}
+
+impl Exception {
+    pub fn parse(date: &str, label: &str) -> Result<Self, ParseError> {
+        let date = NaiveDate::parse_from_str(date.trim(), DATE_FORMAT)
+            .map_err(|_| ParseError(format!("invalid date '{}', expected YYYY-MM-DD", date)))?;
+
+        Ok(Exception {
+            date,
+            label: label.to_string()
+        })
+    }
+
+    pub fn date_key(&self) -> String {
+        self.date.format(DATE_FORMAT).to_string()
+    }
+}
+
+impl Schedule {
+    pub fn is_active(&self, now: NaiveDateTime) -> bool {
+        if self.exceptions.iter().any(|exception| exception.date == now.date()) {
+            return false;
+        }
+
+        self.rules.iter().any(|rule| rule.is_active(now))
+    }
+}
+
+pub fn watch(
+    schedule: Arc<Mutex<Schedule>>,
+    interval: Duration,
+    proxy: EventLoopProxy<UserEvent>
+) {
+    thread::spawn(move || {
+        let mut last: Option<bool> = None;
+
+        loop {
+            let active = schedule.lock()
+                .map(|schedule| schedule.is_active(Local::now().naive_local()))
+                .unwrap_or(false);
+
+            if last != Some(active) {
+                if proxy.send_event(UserEvent::TriggerEvent(Hold::Schedule, active)).is_err() {
+                    break;
+                }
+                last = Some(active);
+            }
+
+            thread::sleep(interval);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::new(
+            NaiveDate::parse_from_str(date, DATE_FORMAT).unwrap(),
+            parse_time(time).unwrap()
+        )
+    }
+
+    #[test]
+    fn parses_day_ranges() {
+        let rule: Rule = "mon-wed,fri 08:30-18:00".parse().unwrap();
+        assert_eq!(rule.to_string(), "mon,tue,wed,fri 08:30-18:00");
+
+        let rule: Rule = "fri-mon 10:00-12:00".parse().unwrap();
+        assert_eq!(rule.to_string(), "mon,fri,sat,sun 10:00-12:00");
+
+        assert!("mon-funday 08:30-18:00".parse::<Rule>().is_err());
+        assert!("mon 08:30".parse::<Rule>().is_err());
+        assert!("mon 8:30-25:00".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn is_active_within_the_day() {
+        let rule: Rule = "mon-fri 08:30-18:00".parse().unwrap();
+
+        assert!(rule.is_active(at("2025-06-02", "08:30")));
+        assert!(rule.is_active(at("2025-06-06", "17:59")));
+        assert!(!rule.is_active(at("2025-06-02", "18:00")));
+        assert!(!rule.is_active(at("2025-06-02", "08:29")));
+        assert!(!rule.is_active(at("2025-06-07", "12:00")));
+    }
+
+    #[test]
+    fn wraps_past_midnight() {
+        let rule: Rule = "fri 22:00-02:00".parse().unwrap();
+
+        assert!(rule.is_active(at("2025-06-06", "22:00")));
+        assert!(rule.is_active(at("2025-06-07", "01:59")));
+        assert!(!rule.is_active(at("2025-06-07", "02:00")));
+        assert!(!rule.is_active(at("2025-06-07", "22:30")));
+        assert!(!rule.is_active(at("2025-06-06", "01:00")));
+    }
+
+    #[test]
+    fn equal_start_and_end_cover_a_whole_day() {
+        let rule: Rule = "sat,sun 00:00-00:00".parse().unwrap();
+
+        assert!(rule.is_active(at("2025-06-07", "00:00")));
+        assert!(rule.is_active(at("2025-06-08", "23:59")));
+        assert!(!rule.is_active(at("2025-06-09", "00:00")));
+        assert!(!rule.is_active(at("2025-06-06", "23:59")));
+
+        let rule: Rule = "mon 09:00-09:00".parse().unwrap();
+
+        assert!(rule.is_active(at("2025-06-02", "09:00")));
+        assert!(rule.is_active(at("2025-06-03", "08:59")));
+        assert!(!rule.is_active(at("2025-06-03", "09:00")));
+    }
+
+    #[test]
+    fn exceptions_override_rules() {
+        let schedule = Schedule {
+            rules: vec!["mon-fri 08:30-18:00".parse().unwrap()],
+            exceptions: vec![Exception::parse("2025-12-25", "Christmas").unwrap()]
+        };
+
+        assert!(schedule.is_active(at("2025-12-24", "12:00")));
+        assert!(!schedule.is_active(at("2025-12-25", "12:00")));
+    }
+}
@@ -0,0 +1,170 @@
+use std::thread;
+use tao::event_loop::EventLoopProxy;
+
+#[cfg(target_os = "windows")]
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM},
+        System::{
+            LibraryLoader::GetModuleHandleW,
+            RemoteDesktop::{
+                WTSRegisterSessionNotification, NOTIFY_FOR_THIS_SESSION,
+                WTS_SESSION_LOCK, WTS_SESSION_UNLOCK
+            }
+        },
+        UI::WindowsAndMessaging::{
+            CreateWindowExW, DefWindowProcW, DispatchMessageW,
+            GetMessageW, GetWindowLongPtrW, RegisterClassW,
+            SetWindowLongPtrW, TranslateMessage, GWLP_USERDATA,
+            MSG, PBT_APMRESUMEAUTOMATIC, PBT_APMSUSPEND,
+            WINDOW_EX_STYLE, WM_POWERBROADCAST, WM_WTSSESSION_CHANGE,
+            WNDCLASSW, WS_OVERLAPPED
+        }
+    }
+};
+
+#[cfg(target_os = "linux")]
+use std::{
+    env,
+    io::{BufRead, BufReader},
+    process::{Command, Stdio}
+};
+
+use crate::UserEvent;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SessionEvent {
+    Suspend,
+    Resume,
+    Lock,
+    Unlock
+}
+
+#[cfg(target_os = "windows")]
+unsafe extern "system" fn window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM
+) -> LRESULT {
+    let event = match (msg, wparam.0 as u32) {
+        (WM_POWERBROADCAST, PBT_APMSUSPEND) => Some(SessionEvent::Suspend),
+        (WM_POWERBROADCAST, PBT_APMRESUMEAUTOMATIC) => Some(SessionEvent::Resume),
+        (WM_WTSSESSION_CHANGE, WTS_SESSION_LOCK) => Some(SessionEvent::Lock),
+        (WM_WTSSESSION_CHANGE, WTS_SESSION_UNLOCK) => Some(SessionEvent::Unlock),
+        _ => None
+    };
+
+    unsafe {
+        let proxy = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *const EventLoopProxy<UserEvent>;
+        if let (Some(event), false) = (event, proxy.is_null()) {
+            let _ = (*proxy).send_event(UserEvent::SessionEvent(event));
+        }
+
+        DefWindowProcW(hwnd, msg, wparam, lparam)
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn watch(proxy: EventLoopProxy<UserEvent>) {
+    thread::spawn(move || unsafe {
+        let Ok(instance) = GetModuleHandleW(None) else {
+            return;
+        };
+
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(window_proc),
+            hInstance: instance.into(),
+            lpszClassName: w!("kava_session"),
+            ..Default::default()
+        };
+        if RegisterClassW(&class) == 0 {
+            return;
+        }
+
+        let Ok(hwnd) = CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("kava_session"),
+            w!("kava"),
+            WS_OVERLAPPED,
+            0, 0, 0, 0,
+            None,
+            None,
+            Some(instance.into()),
+            None
+        ) else {
+            return;
+        };
+
+        SetWindowLongPtrW(hwnd, GWLP_USERDATA, Box::into_raw(Box::new(proxy)) as isize);
+        let _ = WTSRegisterSessionNotification(hwnd, NOTIFY_FOR_THIS_SESSION);
+
+        let mut msg = MSG::default();
+        while GetMessageW(&mut msg, None, 0, 0).as_bool() {
+            let _ = TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+    });
+}
+
+#[cfg(target_os = "linux")]
+fn session_path(id: &str) -> String {
+    let escaped: String = id.bytes()
+        .enumerate()
+        .map(|(i, byte)| match byte {
+            b'0'..=b'9' if i > 0 => (byte as char).to_string(),
+            b'a'..=b'z' | b'A'..=b'Z' => (byte as char).to_string(),
+            _ => format!("_{:02x}", byte)
+        })
+        .collect();
+
+    format!("/org/freedesktop/login1/session/{}", escaped)
+}
+
+#[cfg(target_os = "linux")]
+fn parse_signal(line: &str, session: Option<&str>) -> Option<SessionEvent> {
+    let (path, signal) = line.split_once(": ")?;
+    let (member, args) = signal.split_once(' ').unwrap_or((signal, ""));
+
+    match member {
+        "org.freedesktop.login1.Manager.PrepareForSleep" => Some(match args.contains("true") {
+            true => SessionEvent::Suspend,
+            false => SessionEvent::Resume
+        }),
+        "org.freedesktop.login1.Session.Lock" if session.map_or(true, |session| session == path) => {
+            Some(SessionEvent::Lock)
+        }
+        "org.freedesktop.login1.Session.Unlock" if session.map_or(true, |session| session == path) => {
+            Some(SessionEvent::Unlock)
+        }
+        _ => None
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn watch(proxy: EventLoopProxy<UserEvent>) {
+    thread::spawn(move || {
+        let Ok(mut child) = Command::new("gdbus")
+            .args(["monitor", "--system", "--dest", "org.freedesktop.login1"])
+            .stdout(Stdio::piped())
+            .spawn() else {
+            return;
+        };
+        let Some(stdout) = child.stdout.take() else {
+            return;
+        };
+
+        let session = env::var("XDG_SESSION_ID").ok().map(|id| session_path(&id));
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let Some(event) = parse_signal(&line, session.as_deref()) else {
+                continue;
+            };
+            if proxy.send_event(UserEvent::SessionEvent(event)).is_err() {
+                break;
+            }
+        }
+
+        let _ = child.kill();
+    });
+}
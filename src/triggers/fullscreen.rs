@@ -0,0 +1,35 @@
+use crate::foreground::{self, WindowInfo};
+
+#[derive(Clone, Default)]
+pub struct AppFilter {
+    pub allow: Vec<String>,
+    pub deny: Vec<String>
+}
+
+impl AppFilter {
+    pub fn parse(allow: &[String], deny: &[String]) -> Self {
+        let list = |value: &[String]| value.iter()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        AppFilter {
+            allow: list(allow),
+            deny: list(deny)
+        }
+    }
+
+    pub fn permits(&self, window: &WindowInfo) -> bool {
+        let allowed = self.allow.is_empty() || self.allow.iter().any(|app| window.is_app(app));
+        let denied = self.deny.iter().any(|app| window.is_app(app));
+
+        allowed && !denied
+    }
+}
+
+pub fn is_active(filter: &AppFilter) -> bool {
+    foreground::fullscreen()
+        .iter()
+        .any(|window| filter.permits(window))
+}
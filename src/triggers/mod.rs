@@ -0,0 +1,34 @@
+use std::{thread, time::Duration};
+use tao::event_loop::EventLoopProxy;
+
+use crate::{holds::Hold, UserEvent};
+
+pub mod fullscreen;
+pub mod network;
+pub mod remote_session;
+pub mod rules;
+pub mod sentinel;
+pub mod usb;
+
+pub fn watch(
+    hold: Hold,
+    interval: Duration,
+    proxy: EventLoopProxy<UserEvent>,
+    mut probe: impl FnMut() -> bool + Send + 'static
+) {
+    thread::spawn(move || {
+        let mut last: Option<bool> = None;
+
+        loop {
+            let triggered = probe();
+            if last != Some(triggered) {
+                if proxy.send_event(UserEvent::TriggerEvent(hold, triggered)).is_err() {
+                    break;
+                }
+                last = Some(triggered);
+            }
+
+            thread::sleep(interval);
+        }
+    });
+}
@@ -0,0 +1,158 @@
+use std::{fmt, net::Ipv4Addr, str::FromStr};
+
+use crate::{
+    network::{self, NetworkInfo},
+    parse::ParseError
+};
+
+#[derive(Clone, Debug)]
+enum Matcher {
+    Ssid(String),
+    GatewayMac(String),
+    Subnet(Ipv4Addr, u8)
+}
+
+#[derive(Clone, Debug)]
+pub struct Location {
+    pub name: String,
+    pub profile: Option<String>,
+    matcher: Matcher
+}
+
+fn parse_subnet(value: &str) -> Result<(Ipv4Addr, u8), ParseError> {
+    let error = || ParseError(format!("invalid subnet '{}', expected e.g. 10.0.0.0/8", value));
+
+    let (address, prefix) = value.split_once('/').ok_or_else(error)?;
+    let address = address.parse::<Ipv4Addr>().map_err(|_| error())?;
+    let prefix = prefix.parse::<u8>().ok().filter(|prefix| *prefix <= 32).ok_or_else(error)?;
+
+    Ok((address, prefix))
+}
+
+impl Location {
+    pub fn matches(&self, network: &NetworkInfo) -> bool {
+        match &self.matcher {
+            Matcher::Ssid(ssid) => network.ssid.as_deref() == Some(ssid.as_str()),
+            Matcher::GatewayMac(mac) => network.gateway_mac.as_deref() == Some(mac.as_str()),
+            Matcher::Subnet(address, prefix) => network.gateway.is_some_and(|gateway| {
+                let mask = u32::MAX.checked_shl(32 - *prefix as u32).unwrap_or(0);
+                u32::from(gateway) & mask == u32::from(*address) & mask
+            })
+        }
+    }
+}
+
+impl FromStr for Location {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (location, profile) = match s.rsplit_once(" => ") {
+            Some((location, profile)) if !profile.trim().is_empty() => (location, Some(profile.trim().to_string())),
+            Some(_) => return Err(ParseError(format!("expected a profile name after '=>', got '{}'", s))),
+            None => (s, None)
+        };
+
+        let mut parts = location.trim().splitn(3, ' ');
+        let (Some(name), Some(kind), Some(value)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(ParseError(format!("expected '<name> <ssid|gateway-mac|subnet> <value> [=> <profile>]', got '{}'", s)));
+        };
+
+        let matcher = match kind {
+            "ssid" => Matcher::Ssid(value.to_string()),
+            "gateway-mac" => Matcher::GatewayMac(network::normalize_mac(value)),
+            "subnet" => {
+                let (address, prefix) = parse_subnet(value)?;
+                Matcher::Subnet(address, prefix)
+            }
+            _ => return Err(ParseError(format!("unknown matcher '{}', expected 'ssid', 'gateway-mac' or 'subnet'", kind)))
+        };
+
+        Ok(Location {
+            name: name.to_string(),
+            profile,
+            matcher
+        })
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.matcher {
+            Matcher::Ssid(ssid) => write!(f, "{} ssid {}", self.name, ssid)?,
+            Matcher::GatewayMac(mac) => write!(f, "{} gateway-mac {}", self.name, mac)?,
+            Matcher::Subnet(address, prefix) => write!(f, "{} subnet {}/{}", self.name, address, prefix)?
+        }
+
+        match &self.profile {
+            Some(profile) => write!(f, " => {}", profile),
+            None => Ok(())
+        }
+    }
+}
+
+pub fn parse_locations(value: &[String]) -> Vec<Location> {
+    value.iter()
+        .filter_map(|line| line.parse::<Location>().ok())
+        .collect()
+}
+
+pub fn matching(locations: &[Location]) -> Option<Location> {
+    if locations.is_empty() {
+        return None;
+    }
+
+    let network = network::current();
+    locations.iter()
+        .find(|location| location.matches(&network))
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn office() -> NetworkInfo {
+        NetworkInfo {
+            ssid: Some("Corp WiFi".to_string()),
+            gateway: Some(Ipv4Addr::new(10, 1, 2, 1)),
+            gateway_mac: Some("00:11:22:33:44:55".to_string())
+        }
+    }
+
+    #[test]
+    fn parses_locations_with_profiles() {
+        let location: Location = "office ssid Corp WiFi => Overnight render".parse().unwrap();
+
+        assert_eq!(location.name, "office");
+        assert_eq!(location.profile.as_deref(), Some("Overnight render"));
+        assert!(location.matches(&office()));
+        assert_eq!(location.to_string(), "office ssid Corp WiFi => Overnight render");
+    }
+
+    #[test]
+    fn parses_locations_without_profiles() {
+        let location: Location = "lab gateway-mac 00-11-22-33-44-55".parse().unwrap();
+
+        assert_eq!(location.profile, None);
+        assert!(location.matches(&office()));
+        assert_eq!(location.to_string(), "lab gateway-mac 00:11:22:33:44:55");
+    }
+
+    #[test]
+    fn matches_subnets() {
+        let inside: Location = "campus subnet 10.0.0.0/8".parse().unwrap();
+        let outside: Location = "home subnet 192.168.1.0/24".parse().unwrap();
+
+        assert!(inside.matches(&office()));
+        assert!(!outside.matches(&office()));
+        assert!(!outside.matches(&NetworkInfo::default()));
+    }
+
+    #[test]
+    fn rejects_invalid_locations() {
+        assert!("office ssid".parse::<Location>().is_err());
+        assert!("office ssid Corp =>  ".parse::<Location>().is_err());
+        assert!("office bssid Corp".parse::<Location>().is_err());
+        assert!("office subnet 10.0.0.0/33".parse::<Location>().is_err());
+    }
+}
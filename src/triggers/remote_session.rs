@@ -0,0 +1,214 @@
+#[cfg(target_os = "windows")]
+use windows::Win32::{
+    NetworkManagement::IpHelper::{
+        GetExtendedTcpTable, MIB_TCPROW_OWNER_PID,
+        MIB_TCPTABLE_OWNER_PID, MIB_TCP_STATE_ESTAB,
+        TCP_TABLE_OWNER_PID_CONNECTIONS
+    },
+    System::RemoteDesktop::{
+        WTSEnumerateSessionsW, WTSFreeMemory,
+        WTSActive, WTS_CURRENT_SERVER_HANDLE,
+        WTS_SESSION_INFOW
+    }
+};
+
+const SSH_PORT: u16 = 22;
+
+#[cfg(target_os = "windows")]
+const AF_INET: u32 = 2;
+#[cfg(target_os = "windows")]
+const RDP_WINSTATION_PREFIX: &str = "RDP-Tcp#";
+
+#[cfg(target_os = "linux")]
+const UTMP_PATHS: [&str; 2] = ["/run/utmp", "/var/run/utmp"];
+#[cfg(any(test, target_os = "linux"))]
+const UTMP_RECORD_SIZE: usize = 384;
+#[cfg(any(test, target_os = "linux"))]
+const UTMP_USER_PROCESS: i16 = 7;
+#[cfg(any(test, target_os = "linux"))]
+const UTMP_HOST: std::ops::Range<usize> = 76..332;
+#[cfg(any(test, target_os = "linux"))]
+const LOCAL_HOST_PREFIXES: [&str; 2] = [":", "tmux("];
+
+pub fn is_active() -> bool {
+    active_sessions() > 0
+}
+
+#[cfg(target_os = "windows")]
+pub fn active_sessions() -> usize {
+    rdp_sessions() + ssh_connections()
+}
+
+#[cfg(target_os = "windows")]
+fn rdp_sessions() -> usize {
+    let mut sessions: *mut WTS_SESSION_INFOW = std::ptr::null_mut();
+    let mut count: u32 = 0;
+
+    unsafe {
+        if WTSEnumerateSessionsW(
+            Some(WTS_CURRENT_SERVER_HANDLE),
+            0,
+            1,
+            &mut sessions,
+            &mut count
+        ).is_err() {
+            return 0;
+        }
+
+        let active = std::slice::from_raw_parts(sessions, count as usize)
+            .iter()
+            .filter(|session| session.State == WTSActive)
+            .filter(|session| {
+                session.pWinStationName
+                    .to_string()
+                    .map(|name| name.starts_with(RDP_WINSTATION_PREFIX))
+                    .unwrap_or(false)
+            })
+            .count();
+
+        WTSFreeMemory(sessions as _);
+        active
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn ssh_connections() -> usize {
+    let mut size: u32 = 0;
+
+    unsafe {
+        GetExtendedTcpTable(
+            None,
+            &mut size,
+            false,
+            AF_INET,
+            TCP_TABLE_OWNER_PID_CONNECTIONS,
+            0
+        );
+
+        let mut buffer: Vec<u8> = vec![0; size as usize];
+        if GetExtendedTcpTable(
+            Some(buffer.as_mut_ptr() as _),
+            &mut size,
+            false,
+            AF_INET,
+            TCP_TABLE_OWNER_PID_CONNECTIONS,
+            0
+        ) != 0 {
+            return 0;
+        }
+
+        let table = buffer.as_ptr() as *const MIB_TCPTABLE_OWNER_PID;
+        let rows: &[MIB_TCPROW_OWNER_PID] = std::slice::from_raw_parts(
+            (*table).table.as_ptr(),
+            (*table).dwNumEntries as usize
+        );
+
+        rows.iter()
+            .filter(|row| row.dwState == MIB_TCP_STATE_ESTAB.0 as u32)
+            .filter(|row| u16::from_be(row.dwLocalPort as u16) == SSH_PORT)
+            .count()
+    }
+}
+
+// terminal multiplexers register their windows with a host like ":S.0" (screen) or "tmux(1234).%0"
+#[cfg(any(test, target_os = "linux"))]
+fn is_remote_host(host: &str) -> bool {
+    !host.is_empty() && !LOCAL_HOST_PREFIXES.iter().any(|prefix| host.starts_with(prefix))
+}
+
+#[cfg(any(test, target_os = "linux"))]
+fn count_remote_logins(records: &[u8], is_running: impl Fn(i32) -> bool) -> usize {
+    records.chunks_exact(UTMP_RECORD_SIZE)
+        .filter(|record| i16::from_ne_bytes([record[0], record[1]]) == UTMP_USER_PROCESS)
+        .filter(|record| is_running(i32::from_ne_bytes([record[4], record[5], record[6], record[7]])))
+        .filter(|record| {
+            let host = &record[UTMP_HOST];
+            let host = &host[..host.iter().position(|byte| *byte == 0).unwrap_or(host.len())];
+            is_remote_host(&String::from_utf8_lossy(host))
+        })
+        .count()
+}
+
+#[cfg(any(test, target_os = "linux"))]
+fn count_ssh(table: &str) -> usize {
+    table.lines()
+        .skip(1)
+        .filter(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            fields.len() > 3 &&
+                fields[3] == "01" &&
+                fields[1].rsplit(':')
+                    .next()
+                    .and_then(|port| u16::from_str_radix(port, 16).ok()) == Some(SSH_PORT)
+        })
+        .count()
+}
+
+#[cfg(target_os = "linux")]
+pub fn active_sessions() -> usize {
+    let records = UTMP_PATHS.iter()
+        .find_map(|path| std::fs::read(path).ok())
+        .unwrap_or_default();
+
+    let remote_logins = count_remote_logins(&records, |pid| {
+        std::path::Path::new(&format!("/proc/{}", pid)).exists()
+    });
+
+    if remote_logins > 0 {
+        remote_logins
+    } else {
+        ssh_connections()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn ssh_connections() -> usize {
+    ["/proc/net/tcp", "/proc/net/tcp6"].iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .map(|table| count_ssh(&table))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TCP: &str = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 21351 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:0277 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 19828 1 0000000000000000 100 0 0 10 0
+   2: 0A00020F:0016 0A000202:D4C2 01 00000000:00000000 02:000A7B2A 00000000     0        0 42113 2 0000000000000000 20 4 29 10 -1
+   3: 0A00020F:A1F4 5DB8D822:0016 01 00000000:00000000 02:0009F2C4 00000000  1000        0 43911 1 0000000000000000 20 4 30 10 -1
+";
+
+    fn record(kind: i16, pid: i32, host: &str) -> Vec<u8> {
+        let mut record = vec![0u8; UTMP_RECORD_SIZE];
+        record[0..2].copy_from_slice(&kind.to_ne_bytes());
+        record[4..8].copy_from_slice(&pid.to_ne_bytes());
+        record[UTMP_HOST.start..UTMP_HOST.start + host.len()].copy_from_slice(host.as_bytes());
+        record
+    }
+
+    #[test]
+    fn counts_remote_logins_from_utmp() {
+        let records = [
+            record(UTMP_USER_PROCESS, 100, "192.168.1.20"),
+            record(UTMP_USER_PROCESS, 101, ""),
+            record(UTMP_USER_PROCESS, 102, ":0"),
+            record(UTMP_USER_PROCESS, 103, "tmux(2211).%0"),
+            record(UTMP_USER_PROCESS, 104, ":S.0"),
+            record(UTMP_USER_PROCESS, 105, "laptop.lan"),
+            record(8, 106, "10.0.0.5")
+        ].concat();
+
+        assert_eq!(count_remote_logins(&records, |_| true), 2);
+        assert_eq!(count_remote_logins(&records, |pid| pid != 105), 1);
+        assert_eq!(count_remote_logins(&records[..UTMP_RECORD_SIZE - 1], |_| true), 0);
+    }
+
+    #[test]
+    fn counts_established_ssh_connections() {
+        assert_eq!(count_ssh(TCP), 1);
+        assert_eq!(count_ssh(""), 0);
+    }
+}
@@ -0,0 +1,113 @@
+use std::{fmt, str::FromStr};
+use regex::Regex;
+
+use crate::{
+    foreground::{self, WindowInfo},
+    parse::ParseError
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Scope {
+    Screen,
+    System
+}
+
+#[derive(Clone, Debug)]
+enum Target {
+    Title(Regex),
+    App(String)
+}
+
+#[derive(Clone, Debug)]
+pub struct Rule {
+    scope: Scope,
+    target: Target
+}
+
+impl Rule {
+    pub fn matches(&self, window: &WindowInfo) -> bool {
+        match &self.target {
+            Target::Title(pattern) => pattern.is_match(&window.title),
+            Target::App(app) => window.is_app(app)
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().splitn(3, ' ');
+        let (Some(scope), Some(target), Some(pattern)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(ParseError(format!("expected '<screen|system> <title|app> <pattern>', got '{}'", s)));
+        };
+
+        let scope = match scope {
+            "screen" => Scope::Screen,
+            "system" => Scope::System,
+            _ => return Err(ParseError(format!("unknown scope '{}', expected 'screen' or 'system'", scope)))
+        };
+
+        let target = match target {
+            "title" => Target::Title(
+                Regex::new(pattern).map_err(|err| ParseError(format!("invalid title pattern: {}", err)))?
+            ),
+            "app" => Target::App(pattern.to_string()),
+            _ => return Err(ParseError(format!("unknown target '{}', expected 'title' or 'app'", target)))
+        };
+
+        Ok(Rule { scope, target })
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scope = match self.scope {
+            Scope::Screen => "screen",
+            Scope::System => "system"
+        };
+
+        match &self.target {
+            Target::Title(pattern) => write!(f, "{} title {}", scope, pattern.as_str()),
+            Target::App(app) => write!(f, "{} app {}", scope, app)
+        }
+    }
+}
+
+pub fn parse_rules(value: &[String]) -> Vec<Rule> {
+    value.iter()
+        .filter_map(|line| line.parse::<Rule>().ok())
+        .collect()
+}
+
+pub fn is_active(rules: &[Rule], scope: Scope) -> bool {
+    let Some(window) = foreground::foreground() else {
+        return false;
+    };
+
+    rules.iter()
+        .filter(|rule| rule.scope == scope)
+        .any(|rule| rule.matches(&window))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_displays_rules() {
+        let rule: Rule = "screen title .*Zoom Meeting.*".parse().unwrap();
+        assert_eq!(rule.scope, Scope::Screen);
+        assert_eq!(rule.to_string(), "screen title .*Zoom Meeting.*");
+
+        let window = WindowInfo {
+            title: "Zoom Meeting - Weekly".to_string(),
+            process: "zoom.exe".to_string()
+        };
+        assert!(rule.matches(&window));
+        assert!("system app zoom".parse::<Rule>().unwrap().matches(&window));
+
+        assert!("screen title (".parse::<Rule>().is_err());
+        assert!("everything app zoom".parse::<Rule>().is_err());
+    }
+}
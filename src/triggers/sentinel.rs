@@ -0,0 +1,138 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH}
+};
+use chrono::DateTime;
+
+pub const EXTENSION: &str = "kava-hold";
+
+pub struct Sentinel {
+    pub expires: Option<u64>,
+    pub pid: Option<u32>,
+    pub reason: Option<String>
+}
+
+#[cfg(target_os = "windows")]
+fn is_process_running(pid: u32) -> bool {
+    use windows::Win32::{
+        Foundation::{CloseHandle, ERROR_INVALID_PARAMETER, STILL_ACTIVE},
+        System::Threading::{
+            GetExitCodeProcess, OpenProcess,
+            PROCESS_QUERY_LIMITED_INFORMATION
+        }
+    };
+
+    unsafe {
+        let process = match OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) {
+            Ok(process) => process,
+            Err(err) => return err.code() != ERROR_INVALID_PARAMETER.to_hresult()
+        };
+
+        let mut exit_code: u32 = 0;
+        let running = GetExitCodeProcess(process, &mut exit_code).is_ok() &&
+            exit_code == STILL_ACTIVE.0 as u32;
+        let _ = CloseHandle(process);
+
+        running
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn is_process_running(pid: u32) -> bool {
+    Path::new(&format!("/proc/{}", pid)).exists()
+}
+
+impl Sentinel {
+    pub fn read(path: &Path) -> Option<Self> {
+        let contents = fs::read_to_string(path).ok()?;
+        let mut sentinel = Sentinel {
+            expires: None,
+            pid: None,
+            reason: None
+        };
+
+        for line in contents.lines() {
+            match line.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+                Some(("pid", value)) => sentinel.pid = value.parse().ok(),
+                Some(("reason", value)) if !value.is_empty() => sentinel.reason = Some(value.to_string()),
+                Some(("expires", value)) => {
+                    sentinel.expires = value.parse::<u64>().ok().or_else(|| {
+                        DateTime::parse_from_rfc3339(value)
+                            .ok()
+                            .and_then(|expires| u64::try_from(expires.timestamp()).ok())
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Some(sentinel)
+    }
+
+    pub fn is_stale(&self) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|now| now.as_secs())
+            .unwrap_or(0);
+
+        self.expires.is_some_and(|expires| expires <= now) ||
+            self.pid.is_some_and(|pid| !is_process_running(pid))
+    }
+}
+
+pub fn active(directories: &[PathBuf]) -> Vec<String> {
+    let mut reasons = Vec::new();
+
+    for directory in directories {
+        let Ok(entries) = fs::read_dir(directory) else {
+            continue;
+        };
+
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().and_then(|ext| ext.to_str()) != Some(EXTENSION) {
+                continue;
+            }
+
+            match Sentinel::read(&path) {
+                Some(sentinel) if sentinel.is_stale() => {
+                    let _ = fs::remove_file(&path);
+                }
+                sentinel => reasons.push(
+                    sentinel.and_then(|sentinel| sentinel.reason)
+                        .unwrap_or_else(|| path.display().to_string())
+                )
+            }
+        }
+    }
+
+    reasons.sort();
+    reasons
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn reports_reasons_and_removes_stale_files() {
+        let directory = env::temp_dir().join(format!("kava-sentinels-{}", process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+
+        fs::write(directory.join("backup.kava-hold"), format!("pid={}\nreason=Nightly backup\n", process::id())).unwrap();
+        fs::write(directory.join("render.kava-hold"), "").unwrap();
+        fs::write(directory.join("expired.kava-hold"), "expires=2001-01-01T00:00:00Z\nreason=Old job").unwrap();
+
+        let reasons = active(std::slice::from_ref(&directory));
+
+        assert_eq!(reasons.len(), 2);
+        assert!(reasons.contains(&"Nightly backup".to_string()));
+        assert!(reasons.contains(&directory.join("render.kava-hold").display().to_string()));
+        assert!(!directory.join("expired.kava-hold").exists());
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
@@ -0,0 +1,488 @@
+use std::{
+    collections::HashMap,
+    env, fmt, fs,
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration
+};
+use tao::event_loop::EventLoopProxy;
+
+#[cfg(target_os = "windows")]
+use std::sync::mpsc::{channel, Receiver, Sender};
+#[cfg(target_os = "windows")]
+use windows::{
+    core::PCWSTR,
+    Win32::Devices::{
+        DeviceAndDriverInstallation::{
+            CM_Get_Device_Interface_ListW, CM_Get_Device_Interface_List_SizeW,
+            CM_Register_Notification, CM_Unregister_Notification, CM_NOTIFY_ACTION,
+            CM_NOTIFY_ACTION_DEVICEINTERFACEARRIVAL, CM_NOTIFY_ACTION_DEVICEINTERFACEREMOVAL,
+            CM_NOTIFY_EVENT_DATA, CM_NOTIFY_FILTER, CM_NOTIFY_FILTER_TYPE_DEVICEINTERFACE,
+            CM_GET_DEVICE_INTERFACE_LIST_PRESENT, CR_SUCCESS, HCMNOTIFICATION
+        },
+        Usb::GUID_DEVINTERFACE_USB_DEVICE
+    }
+};
+
+use crate::{holds::Hold, parse::ParseError, UserEvent};
+
+const REPLAY_ENV: &str = "KAVA_USB_REPLAY";
+
+#[cfg(target_os = "linux")]
+const UDEV_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct UsbId {
+    pub vendor: u16,
+    pub product: u16
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum UsbEvent {
+    Added(String, UsbId),
+    Removed(String)
+}
+
+#[derive(Default)]
+struct Connected(HashMap<String, UsbId>);
+
+pub trait UsbSource {
+    fn next_events(&mut self, timeout: Duration) -> Option<Vec<UsbEvent>>;
+}
+
+impl FromStr for UsbId {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseError(format!("invalid USB id '{}', expected <vendor>:<product> in hex, e.g. 046d:c52b", s));
+
+        let (vendor, product) = s.trim().split_once(':').ok_or_else(error)?;
+        Ok(UsbId {
+            vendor: u16::from_str_radix(vendor, 16).map_err(|_| error())?,
+            product: u16::from_str_radix(product, 16).map_err(|_| error())?
+        })
+    }
+}
+
+impl fmt::Display for UsbId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04x}:{:04x}", self.vendor, self.product)
+    }
+}
+
+impl Connected {
+    fn apply(&mut self, events: Vec<UsbEvent>) {
+        for event in events {
+            match event {
+                UsbEvent::Added(path, id) => {
+                    self.0.insert(path, id);
+                }
+                UsbEvent::Removed(path) => {
+                    self.0.remove(&path);
+                }
+            }
+        }
+    }
+
+    fn any(&self, watched: &[UsbId]) -> bool {
+        self.0.values().any(|id| watched.contains(id))
+    }
+}
+
+pub fn parse_ids(value: &[String]) -> Vec<UsbId> {
+    value.iter()
+        .filter_map(|line| line.parse::<UsbId>().ok())
+        .collect()
+}
+
+pub struct ReplaySource {
+    lines: std::vec::IntoIter<String>
+}
+
+impl ReplaySource {
+    pub fn new(path: PathBuf) -> Self {
+        let lines: Vec<String> = fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .map(str::to_string)
+            .collect();
+
+        Self {
+            lines: lines.into_iter()
+        }
+    }
+}
+
+impl UsbSource for ReplaySource {
+    fn next_events(&mut self, _timeout: Duration) -> Option<Vec<UsbEvent>> {
+        loop {
+            let line = self.lines.next()?;
+            let mut parts = line.split_whitespace();
+
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some("add"), Some(path), Some(id)) => {
+                    if let Ok(id) = id.parse() {
+                        return Some(vec![UsbEvent::Added(path.to_string(), id)]);
+                    }
+                }
+                (Some("remove"), Some(path), _) => {
+                    return Some(vec![UsbEvent::Removed(path.to_string())]);
+                }
+                (Some("sleep"), Some(seconds), _) => {
+                    if let Ok(seconds) = seconds.parse() {
+                        thread::sleep(Duration::from_secs(seconds));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub struct SystemSource {
+    socket: Option<udev::MonitorSocket>,
+    enumerated: bool
+}
+
+#[cfg(target_os = "linux")]
+fn udev_id(device: &udev::Device) -> Option<UsbId> {
+    let product = device.property_value("PRODUCT")?.to_str()?;
+    let mut parts = product.split('/');
+
+    Some(UsbId {
+        vendor: u16::from_str_radix(parts.next()?, 16).ok()?,
+        product: u16::from_str_radix(parts.next()?, 16).ok()?
+    })
+}
+
+#[cfg(target_os = "linux")]
+impl SystemSource {
+    pub fn new() -> Self {
+        let socket = udev::MonitorBuilder::new()
+            .and_then(|builder| builder.match_subsystem_devtype("usb", "usb_device"))
+            .and_then(|builder| builder.listen())
+            .ok();
+
+        Self {
+            socket,
+            enumerated: false
+        }
+    }
+
+    fn enumerate(&self) -> Vec<UsbEvent> {
+        let Ok(mut enumerator) = udev::Enumerator::new() else {
+            return Vec::new();
+        };
+        if enumerator.match_subsystem("usb").is_err() {
+            return Vec::new();
+        }
+
+        enumerator.scan_devices()
+            .map(|devices| {
+                devices
+                    .filter(|device| device.devtype().is_some_and(|devtype| devtype == "usb_device"))
+                    .filter_map(|device| {
+                        let id = udev_id(&device)?;
+                        Some(UsbEvent::Added(device.syspath().to_string_lossy().to_string(), id))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl UsbSource for SystemSource {
+    fn next_events(&mut self, timeout: Duration) -> Option<Vec<UsbEvent>> {
+        if !self.enumerated {
+            self.enumerated = true;
+            return Some(self.enumerate());
+        }
+
+        let socket = self.socket.as_ref()?;
+        let started = std::time::Instant::now();
+        loop {
+            let events: Vec<UsbEvent> = socket.iter()
+                .filter_map(|event| {
+                    let device = event.device();
+                    let path = device.syspath().to_string_lossy().to_string();
+
+                    match event.event_type() {
+                        udev::EventType::Add => udev_id(&device).map(|id| UsbEvent::Added(path, id)),
+                        udev::EventType::Remove => Some(UsbEvent::Removed(path)),
+                        _ => None
+                    }
+                })
+                .collect();
+
+            if !events.is_empty() || started.elapsed() >= timeout {
+                return Some(events);
+            }
+            thread::sleep(UDEV_POLL_INTERVAL);
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn interface_id(symbolic_link: &str) -> Option<UsbId> {
+    let upper = symbolic_link.to_ascii_uppercase();
+    let vendor = upper.split("VID_").nth(1)?.get(..4)?;
+    let product = upper.split("PID_").nth(1)?.get(..4)?;
+
+    Some(UsbId {
+        vendor: u16::from_str_radix(vendor, 16).ok()?,
+        product: u16::from_str_radix(product, 16).ok()?
+    })
+}
+
+#[cfg(target_os = "windows")]
+unsafe extern "system" fn on_device_change(
+    _notification: HCMNOTIFICATION,
+    context: *const core::ffi::c_void,
+    action: CM_NOTIFY_ACTION,
+    data: *const CM_NOTIFY_EVENT_DATA,
+    _size: u32
+) -> u32 {
+    unsafe {
+        let tx = &*(context as *const Sender<UsbEvent>);
+        let link = PCWSTR((*data).u.DeviceInterface.SymbolicLink.as_ptr())
+            .to_string()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        let event = match action {
+            CM_NOTIFY_ACTION_DEVICEINTERFACEARRIVAL => interface_id(&link).map(|id| UsbEvent::Added(link, id)),
+            CM_NOTIFY_ACTION_DEVICEINTERFACEREMOVAL => Some(UsbEvent::Removed(link)),
+            _ => None
+        };
+
+        if let Some(event) = event {
+            let _ = tx.send(event);
+        }
+    }
+
+    0
+}
+
+#[cfg(target_os = "windows")]
+pub struct SystemSource {
+    tx: Box<Sender<UsbEvent>>,
+    rx: Receiver<UsbEvent>,
+    notification: Option<HCMNOTIFICATION>,
+    enumerated: bool
+}
+
+#[cfg(target_os = "windows")]
+impl SystemSource {
+    pub fn new() -> Self {
+        let (tx, rx) = channel::<UsbEvent>();
+
+        Self {
+            tx: Box::new(tx),
+            rx,
+            notification: None,
+            enumerated: false
+        }
+    }
+
+    fn enumerate(&self) -> Vec<UsbEvent> {
+        let mut size: u32 = 0;
+
+        unsafe {
+            if CM_Get_Device_Interface_List_SizeW(
+                &mut size,
+                &GUID_DEVINTERFACE_USB_DEVICE,
+                PCWSTR::null(),
+                CM_GET_DEVICE_INTERFACE_LIST_PRESENT
+            ) != CR_SUCCESS {
+                return Vec::new();
+            }
+
+            let mut buffer: Vec<u16> = vec![0; size as usize];
+            if CM_Get_Device_Interface_ListW(
+                &GUID_DEVINTERFACE_USB_DEVICE,
+                PCWSTR::null(),
+                &mut buffer,
+                CM_GET_DEVICE_INTERFACE_LIST_PRESENT
+            ) != CR_SUCCESS {
+                return Vec::new();
+            }
+
+            buffer.split(|c| *c == 0)
+                .filter(|link| !link.is_empty())
+                .map(|link| String::from_utf16_lossy(link).to_ascii_lowercase())
+                .filter_map(|link| interface_id(&link).map(|id| UsbEvent::Added(link, id)))
+                .collect()
+        }
+    }
+
+    fn register(&mut self) {
+        let mut filter = CM_NOTIFY_FILTER {
+            cbSize: std::mem::size_of::<CM_NOTIFY_FILTER>() as u32,
+            FilterType: CM_NOTIFY_FILTER_TYPE_DEVICEINTERFACE,
+            ..Default::default()
+        };
+        filter.u.DeviceInterface.ClassGuid = GUID_DEVINTERFACE_USB_DEVICE;
+
+        let mut notification = HCMNOTIFICATION::default();
+        unsafe {
+            if CM_Register_Notification(
+                &filter,
+                Some(self.tx.as_ref() as *const Sender<UsbEvent> as _),
+                Some(on_device_change),
+                &mut notification
+            ) == CR_SUCCESS {
+                self.notification = Some(notification);
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for SystemSource {
+    fn drop(&mut self) {
+        if let Some(notification) = self.notification.take() {
+            unsafe {
+                let _ = CM_Unregister_Notification(notification);
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl UsbSource for SystemSource {
+    fn next_events(&mut self, timeout: Duration) -> Option<Vec<UsbEvent>> {
+        if !self.enumerated {
+            self.enumerated = true;
+            self.register();
+            return Some(self.enumerate());
+        }
+
+        self.notification?;
+        let mut events: Vec<UsbEvent> = self.rx.recv_timeout(timeout)
+            .into_iter()
+            .collect();
+        events.extend(self.rx.try_iter());
+
+        Some(events)
+    }
+}
+
+pub fn source() -> Box<dyn UsbSource> {
+    match env::var_os(REPLAY_ENV) {
+        Some(path) => Box::new(ReplaySource::new(PathBuf::from(path))),
+        None => Box::new(SystemSource::new())
+    }
+}
+
+pub fn watch(
+    devices: Arc<Mutex<Vec<UsbId>>>,
+    interval: Duration,
+    proxy: EventLoopProxy<UserEvent>
+) {
+    thread::spawn(move || {
+        let mut source = source();
+        let mut connected = Connected::default();
+        let mut last: Option<bool> = None;
+
+        while let Some(events) = source.next_events(interval) {
+            connected.apply(events);
+
+            let watched = devices.lock()
+                .map(|devices| devices.clone())
+                .unwrap_or_default();
+            let attached = connected.any(&watched);
+
+            if last != Some(attached) {
+                if proxy.send_event(UserEvent::TriggerEvent(Hold::Usb, attached)).is_err() {
+                    break;
+                }
+                last = Some(attached);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADSET: UsbId = UsbId { vendor: 0x046d, product: 0x0a8f };
+
+    fn replay(name: &str, contents: &str, watched: &[UsbId]) -> Vec<bool> {
+        let path = env::temp_dir().join(format!("kava-usb-{}-{}", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+
+        let mut source = ReplaySource::new(path.clone());
+        let mut connected = Connected::default();
+        let mut transitions = Vec::new();
+        while let Some(events) = source.next_events(Duration::ZERO) {
+            connected.apply(events);
+            if transitions.last() != Some(&connected.any(watched)) {
+                transitions.push(connected.any(watched));
+            }
+        }
+
+        let _ = fs::remove_file(path);
+        transitions
+    }
+
+    #[test]
+    fn tracks_attached_and_detached_devices() {
+        let contents = "\
+add /sys/usb/1-1 046d:c52b
+add /sys/usb/1-2 046d:0a8f
+remove /sys/usb/1-1
+remove /sys/usb/1-2
+add /sys/usb/1-3 046d:0a8f
+";
+        assert_eq!(replay("attach", contents, &[HEADSET]), vec![false, true, false, true]);
+    }
+
+    #[test]
+    fn stays_attached_while_any_watched_device_remains() {
+        let contents = "\
+add /sys/usb/1-1 046d:0a8f
+add /sys/usb/1-2 046d:0a8f
+remove /sys/usb/1-1
+remove /sys/usb/1-2
+";
+        assert_eq!(replay("twice", contents, &[HEADSET]), vec![true, false]);
+    }
+
+    #[test]
+    fn skips_malformed_replay_lines() {
+        let contents = "\
+add /sys/usb/1-1
+add /sys/usb/1-1 zzzz:0a8f
+unplug /sys/usb/1-1
+add /sys/usb/1-1 046d:0a8f
+";
+        assert_eq!(replay("malformed", contents, &[HEADSET]), vec![true]);
+    }
+
+    #[test]
+    fn ignores_unwatched_devices() {
+        let contents = "add /sys/usb/1-1 046d:0a8f\n";
+        assert_eq!(replay("unwatched", contents, &[]), vec![false]);
+    }
+
+    #[test]
+    fn parses_usb_ids() {
+        assert_eq!("046d:C52B".parse::<UsbId>().unwrap(), UsbId { vendor: 0x046d, product: 0xc52b });
+        assert_eq!(" 046d:c52b ".parse::<UsbId>().unwrap().to_string(), "046d:c52b");
+
+        for invalid in ["", "046d", "046d:", ":c52b", "046d-c52b", "1046d:c52b", "046d:xyz", "046d:c52b:01"] {
+            assert!(invalid.parse::<UsbId>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn drops_invalid_ids_from_preferences() {
+        let ids = parse_ids(&["046d:0a8f".to_string(), "headset".to_string()]);
+        assert_eq!(ids, vec![HEADSET]);
+    }
+}
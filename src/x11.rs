@@ -0,0 +1,33 @@
+use std::cell::RefCell;
+use x11rb::{
+    connection::Connection,
+    protocol::xproto::{ConnectionExt, Window},
+    rust_connection::RustConnection
+};
+
+thread_local! {
+    static CONNECTION: RefCell<Option<(RustConnection, usize)>> = const { RefCell::new(None) };
+}
+
+pub fn with_connection<T: Default>(probe: impl FnOnce(&RustConnection, Window) -> T) -> T {
+    CONNECTION.with(|connection| {
+        let mut connection = connection.borrow_mut();
+        if connection.is_none() {
+            *connection = x11rb::connect(None).ok();
+        }
+        let Some((conn, screen)) = connection.as_ref() else {
+            return T::default();
+        };
+
+        let result = probe(conn, conn.setup().roots[*screen].root);
+        let is_alive = conn.get_input_focus()
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .is_some();
+        if !is_alive {
+            *connection = None;
+        }
+
+        result
+    })
+}